@@ -7,14 +7,34 @@ pub fn day20_benchmark(c: &mut Criterion) {
 
     group.bench_function("parsing", |b| b.iter(day20::load_input));
 
-    let input = day20::load_input();
+    let input = day20::load_input().unwrap();
 
     group.bench_function("part1", |b| b.iter(|| day20::solve_part1(&input)));
 
     group.bench_function("part2", |b| {
+        b.iter_batched_ref(|| input.clone(), day20::solve_part2, BatchSize::SmallInput)
+    });
+
+    group.bench_function("part2_analytic", |b| {
+        b.iter_batched_ref(
+            || input.clone(),
+            |input| day20::solve_part2_with(input, day20::Engine::Analytic),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("part2_sort_sweep", |b| {
+        b.iter_batched_ref(
+            || input.clone(),
+            |input| day20::solve_part2_with(input, day20::Engine::SortSweep),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.bench_function("part2_parallel", |b| {
         b.iter_batched_ref(
             || input.clone(),
-            |input| day20::solve_part2(input),
+            |input| day20::solve_part2_with(input, day20::Engine::Parallel),
             BatchSize::SmallInput,
         )
     });
@@ -24,25 +44,48 @@ pub fn day20_benchmark(c: &mut Criterion) {
     group.finish()
 }
 
+pub fn day20_generated_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day20_generated");
+
+    // Each run simulates hundreds of ticks of a large swarm, so keep it brief.
+    group.sample_size(10);
+
+    let input: Vec<day20::Particle> = day20::Generator::new(2017)
+        .count(5_000)
+        .pos_range(-1_000..=1_000)
+        .collision(5, 3)
+        .collision(20, 2)
+        .collision(100, 4)
+        .generate();
+
+    for &(name, engine) in &[
+        ("part2", day20::Engine::HashMap),
+        ("part2_sort_sweep", day20::Engine::SortSweep),
+        ("part2_parallel", day20::Engine::Parallel),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched_ref(
+                || input.clone(),
+                |input| day20::solve_part2_with(input, engine),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish()
+}
+
 pub fn day23_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("day23");
 
     group.bench_function("parsing", |b| b.iter(day23::load_input));
 
     group.bench_function("part1", |b| {
-        b.iter_batched_ref(
-            day23::load_input,
-            |input| day23::solve_part1(input),
-            BatchSize::SmallInput,
-        )
+        b.iter_batched_ref(day23::load_input, day23::solve_part1, BatchSize::SmallInput)
     });
 
     group.bench_function("part2", |b| {
-        b.iter_batched_ref(
-            day23::load_input,
-            |input| day23::solve_part2(input),
-            BatchSize::SmallInput,
-        )
+        b.iter_batched_ref(day23::load_input, day23::solve_part2, BatchSize::SmallInput)
     });
 
     group.bench_function("solve", |b| b.iter(day23::solve));
@@ -55,6 +98,10 @@ pub fn day21_benchmark(c: &mut Criterion) {
 
     group.bench_function("solve", |b| b.iter(day21::solve));
 
+    group.bench_function("solve_parallel", |b| {
+        b.iter(|| day21::solve_with(day21::Engine::Parallel))
+    });
+
     group.finish()
 }
 
@@ -79,6 +126,7 @@ criterion_group! {
 
     targets =
         day20_benchmark,
+        day20_generated_benchmark,
         day21_benchmark,
         day23_benchmark,
         day25_benchmark,
@@ -0,0 +1,272 @@
+//! Exact collision detection: every pair of particles is solved for the ticks
+//! at which their trajectories meet, so no amount of simulated time is needed.
+
+use std::{
+    array,
+    collections::{BTreeMap, VecDeque},
+    convert::TryFrom,
+};
+
+use crate::{Particle, ParticleError};
+
+/// The coefficients of `2 * (a.pos_at(t) - b.pos_at(t))` along `axis`,
+/// doubled so that the `t * (t + 1) / 2` term stays integral.
+fn difference<const D: usize>(a: &Particle<D>, b: &Particle<D>, axis: usize) -> [i128; 3] {
+    let da = i128::from(a.accel[axis]) - i128::from(b.accel[axis]);
+    let dv = i128::from(a.vel[axis]) - i128::from(b.vel[axis]);
+    let dp = i128::from(a.pos[axis]) - i128::from(b.pos[axis]);
+
+    [da, 2 * dv + da, 2 * dp]
+}
+
+fn eval([a, b, c]: [i128; 3], t: i128) -> Option<i128> {
+    a.checked_mul(t)?
+        .checked_add(b)?
+        .checked_mul(t)?
+        .checked_add(c)
+}
+
+/// The non-negative integer solutions of `a*t² + b*t + c = 0`, where not every
+/// coefficient is zero, or `None` if the discriminant overflows.
+fn integer_roots([a, b, c]: [i128; 3]) -> Option<Vec<i128>> {
+    let roots = if a == 0 {
+        if b != 0 && c % b == 0 {
+            vec![-c / b]
+        } else {
+            vec![]
+        }
+    } else {
+        let discriminant = b
+            .checked_mul(b)?
+            .checked_sub(a.checked_mul(c)?.checked_mul(4)?)?;
+        if discriminant < 0 {
+            return Some(vec![]);
+        }
+
+        // Neither the square nor the sums below can overflow, as `root` is at
+        // most the square root of `i128::MAX`.
+        let root = discriminant.isqrt();
+        if root * root != discriminant {
+            return Some(vec![]);
+        }
+
+        [-b - root, -b + root]
+            .iter()
+            .filter(|&&num| num % (2 * a) == 0)
+            .map(|&num| num / (2 * a))
+            .collect()
+    };
+
+    Some(roots.into_iter().filter(|&t| t >= 0).collect())
+}
+
+/// Whether every axis' difference is zero at `t`, or `None` if evaluating one
+/// overflows.
+fn meet_at<const D: usize>(axes: &[[i128; 3]; D], t: i128) -> Option<bool> {
+    for &coeffs in axes {
+        if eval(coeffs, t)? != 0 {
+            return Some(false);
+        }
+    }
+    Some(true)
+}
+
+/// The first tick at which `a` and `b` occupy the same position, if any, or
+/// `Err(())` if working it out overflows an `i128` or the tick doesn't fit in
+/// an `i64`.
+pub(crate) fn collision_tick<const D: usize>(
+    a: &Particle<D>,
+    b: &Particle<D>,
+) -> Result<Option<i64>, ()> {
+    let axes: [[i128; 3]; D] = array::from_fn(|axis| difference(a, b, axis));
+
+    // Two identical particles meet right after the first tick.
+    let candidates = match axes.iter().find(|&&coeffs| coeffs != [0; 3]) {
+        Some(&coeffs) => integer_roots(coeffs).ok_or(())?,
+        None => return Ok(Some(1)),
+    };
+
+    // Collisions are only ever checked after a tick, so t = 0 doesn't count.
+    let mut first = None;
+    for t in candidates.into_iter().filter(|&t| t >= 1) {
+        if meet_at(&axes, t).ok_or(())? {
+            first = Some(first.map_or(t, |first: i128| first.min(t)));
+        }
+    }
+
+    first.map(|t| i64::try_from(t).map_err(|_| ())).transpose()
+}
+
+/// Every collision in a swarm, in chronological order.
+///
+/// Yields `(tick, position, particles)` for each point where two or more
+/// particles meet, with `particles` holding their indices in ascending order.
+/// Collisions on the same tick are ordered by position. The iterator ends
+/// once no further collision is possible.
+#[derive(Debug, Clone)]
+pub struct CollisionEvents<'a, const D: usize = 3> {
+    particles: &'a [Particle<D>],
+    /// The first tick each colliding pair meets at, sorted by tick.
+    pairs: Vec<(i64, usize, usize)>,
+    next_pair: usize,
+    destroyed: Vec<bool>,
+    pending: VecDeque<(i64, [i128; D], Vec<usize>)>,
+}
+
+impl<'a, const D: usize> CollisionEvents<'a, D> {
+    /// Solves every pair of `particles` for when they meet.
+    ///
+    /// Fails if that overflows an `i128` for some pair, which only happens
+    /// with coordinates close to the limits of an `i64`, or if a pair meets
+    /// where a position no longer fits in an `i128`.
+    pub fn new(particles: &'a [Particle<D>]) -> Result<Self, ParticleError> {
+        let mut pairs = Vec::new();
+        for (i, a) in particles.iter().enumerate() {
+            for (j, b) in particles.iter().enumerate().skip(i + 1) {
+                let t = collision_tick(a, b)
+                    .map_err(|()| ParticleError::PairOverflow { index: i, other: j })?;
+
+                if let Some(t) = t {
+                    // Collisions are reported along with where they happen.
+                    a.checked_pos_at(t)
+                        .ok_or(ParticleError::Overflow { index: i, t })?;
+                    pairs.push((t, i, j));
+                }
+            }
+        }
+        pairs.sort_unstable();
+
+        Ok(Self {
+            particles,
+            pairs,
+            next_pair: 0,
+            destroyed: vec![false; particles.len()],
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+impl<const D: usize> Iterator for CollisionEvents<'_, D> {
+    type Item = (i64, [i128; D], Vec<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let &(t, _, _) = self.pairs.get(self.next_pair)?;
+
+            // Only particles that made it to this tick can collide on it.
+            let mut piles: BTreeMap<[i128; D], Vec<usize>> = BTreeMap::new();
+            while let Some(&(_, i, j)) = self.pairs.get(self.next_pair).filter(|p| p.0 == t) {
+                self.next_pair += 1;
+
+                if !self.destroyed[i] && !self.destroyed[j] {
+                    let pile = piles.entry(self.particles[i].pos_at(t)).or_default();
+                    pile.push(i);
+                    pile.push(j);
+                }
+            }
+
+            for (pos, mut pile) in piles {
+                pile.sort_unstable();
+                pile.dedup();
+                self.pending.push_back((t, pos, pile));
+            }
+
+            for (_, _, pile) in &self.pending {
+                for &i in pile {
+                    self.destroyed[i] = true;
+                }
+            }
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{particle, solve_part2_with, Engine};
+
+    /// Every 1D particle with small coordinates, so pairs meet early, late,
+    /// twice or never.
+    fn small_swarm() -> Vec<Particle<1>> {
+        let mut particles = Vec::new();
+        for p in -4..=4 {
+            for v in -3..=3 {
+                for a in -2..=2 {
+                    particles.push(particle([p], [v], [a]));
+                }
+            }
+        }
+        particles
+    }
+
+    #[test]
+    fn collision_tick_matches_simulation() {
+        let particles = small_swarm();
+
+        for (i, a) in particles.iter().enumerate() {
+            for b in &particles[i + 1..] {
+                let (mut x, mut y) = (*a, *b);
+                let simulated = (1..=50).find(|_| {
+                    x.tick().unwrap();
+                    y.tick().unwrap();
+                    x.pos == y.pos
+                });
+
+                assert_eq!(collision_tick(a, b), Ok(simulated), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn analytic_engine_matches_simulation() {
+        let swarm = small_swarm();
+
+        let mut simulated = swarm.clone();
+        let mut analytic = swarm;
+        assert_eq!(
+            solve_part2_with(&mut analytic, Engine::Analytic),
+            solve_part2_with(&mut simulated, Engine::HashMap)
+        );
+    }
+
+    #[test]
+    fn analytic_engine_matches_simulation_on_puzzle_input() {
+        let input = crate::load_input().unwrap();
+
+        let mut simulated = input.clone();
+        let mut analytic = input;
+        assert_eq!(
+            solve_part2_with(&mut analytic, Engine::Analytic),
+            solve_part2_with(&mut simulated, Engine::HashMap)
+        );
+    }
+
+    #[test]
+    fn extreme_pairs_report_overflow() {
+        let mut swarm = vec![
+            particle([i64::MAX; 3], [i64::MAX; 3], [i64::MAX; 3]),
+            particle([i64::MIN; 3], [i64::MIN; 3], [i64::MIN; 3]),
+        ];
+        assert_eq!(
+            solve_part2_with(&mut swarm, Engine::Analytic),
+            Err(ParticleError::PairOverflow { index: 0, other: 1 })
+        );
+        assert_eq!(swarm.len(), 2);
+
+        // These two meet on tick 2^40, by which time they're well past where
+        // an `i128` ends.
+        let mut swarm = vec![
+            particle([-(1 << 40)], [1], [1 << 62]),
+            particle([0], [0], [1 << 62]),
+        ];
+        assert_eq!(
+            solve_part2_with(&mut swarm, Engine::Analytic),
+            Err(ParticleError::Overflow {
+                index: 0,
+                t: 1 << 40
+            })
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    env,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    process,
+    str::FromStr,
+};
+
+use day20::{Generator, Particle};
+
+const USAGE: &str = "usage: day20_generate [--seed N] [--count N] [--pos LO..HI] [--vel LO..HI]
+                      [--accel LO..HI] [--collide TICK:SIZE]...";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("day20_generate: {}", message);
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn parse<T: FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+fn parse_pair<A: FromStr, B: FromStr>(value: &str, separator: &str) -> (A, B) {
+    let (a, b) = value.split_once(separator).unwrap_or_else(|| usage());
+    (parse(a), parse(b))
+}
+
+fn parse_range(option: &str, value: &str) -> RangeInclusive<i64> {
+    let (lo, hi) = parse_pair(value, "..");
+    if lo > hi {
+        fail(format_args!("{} {} is an empty range", option, value));
+    }
+    lo..=hi
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let mut args = args.iter().map(String::as_str);
+
+    let mut seed = 0;
+    let mut options: Vec<(&str, &str)> = Vec::new();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg {
+            "--seed" => seed = parse(value),
+            "--count" | "--pos" | "--vel" | "--accel" | "--collide" => options.push((arg, value)),
+            _ => usage(),
+        }
+    }
+
+    let mut generator = Generator::new(seed);
+    for (option, value) in options {
+        generator = match option {
+            "--count" => generator.count(parse(value)),
+            "--pos" => generator.pos_range(parse_range(option, value)),
+            "--vel" => generator.vel_range(parse_range(option, value)),
+            "--accel" => generator.accel_range(parse_range(option, value)),
+            "--collide" => {
+                let (tick, size) = parse_pair(value, ":");
+                if tick < 1 {
+                    fail("collisions can only happen from tick 1 onwards");
+                }
+                if size < 2 {
+                    fail("a collision needs at least two particles");
+                }
+                generator.collision(tick, size)
+            }
+            _ => unreachable!(),
+        };
+    }
+
+    let particles: Vec<Particle> = generator.generate();
+
+    let stdout = io::stdout();
+    let mut stdout = BufWriter::new(stdout.lock());
+    for particle in &particles {
+        writeln!(stdout, "{}", particle).unwrap();
+    }
+    stdout.flush().unwrap();
+
+    eprintln!("expected survivors: {}", generator.expected_survivors());
+}
@@ -0,0 +1,236 @@
+//! Collisions in continuous time: between two ticks every particle moves in a
+//! straight line from one position to the next, and two particles collide if
+//! they're ever at the same point at the same moment.
+
+use std::cmp::Ordering;
+
+use crate::{proof::Prover, retain_alive, until_settled, Particle, ParticleError};
+
+/// A moment within a tick as the fraction `num / den` of the way through it.
+#[derive(Debug, Clone, Copy)]
+struct Moment {
+    num: i128,
+    den: i128,
+}
+
+impl Moment {
+    /// Compares the two fractions one term of their continued fractions at a
+    /// time, since cross-multiplying them can overflow.
+    fn cmp(self, other: Self) -> Ordering {
+        let (mut a, mut b) = ((self.num, self.den), (other.num, other.den));
+        let mut flipped = false;
+
+        loop {
+            let (whole_a, rest_a) = (a.0.div_euclid(a.1), a.0.rem_euclid(a.1));
+            let (whole_b, rest_b) = (b.0.div_euclid(b.1), b.0.rem_euclid(b.1));
+
+            let order = match (whole_a.cmp(&whole_b), rest_a, rest_b) {
+                (Ordering::Equal, 0, 0) => return Ordering::Equal,
+                (Ordering::Equal, 0, _) => Ordering::Less,
+                (Ordering::Equal, _, 0) => Ordering::Greater,
+                (Ordering::Equal, _, _) => {
+                    // rest_a / a.1 < rest_b / b.1 exactly when their
+                    // reciprocals compare the other way around.
+                    a = (a.1, rest_a);
+                    b = (b.1, rest_b);
+                    flipped = !flipped;
+                    continue;
+                }
+                (order, _, _) => order,
+            };
+
+            return if flipped { order.reverse() } else { order };
+        }
+    }
+}
+
+/// When, if at all, the segments `a0 -> a1` and `b0 -> b1` traced over the
+/// same tick meet, excluding its very start but including its end.
+fn meeting<const D: usize>([a0, a1]: [&[i64; D]; 2], [b0, b1]: [&[i64; D]; 2]) -> Option<Moment> {
+    let mut moment: Option<Moment> = None;
+
+    for axis in 0..D {
+        let d0 = i128::from(a0[axis]) - i128::from(b0[axis]);
+        let d1 = i128::from(a1[axis]) - i128::from(b1[axis]);
+        let slope = d1 - d0;
+
+        if slope == 0 {
+            if d0 != 0 {
+                return None;
+            }
+            continue;
+        }
+
+        let here = if slope > 0 {
+            Moment {
+                num: -d0,
+                den: slope,
+            }
+        } else {
+            Moment {
+                num: d0,
+                den: -slope,
+            }
+        };
+
+        match moment {
+            Some(there) if there.cmp(here) != Ordering::Equal => return None,
+            _ => moment = Some(here),
+        }
+    }
+
+    match moment {
+        Some(moment) if 0 < moment.num && moment.num <= moment.den => Some(moment),
+        Some(_) => None,
+        // They move in lockstep, so they've been touching since the start.
+        None => Some(Moment { num: 0, den: 1 }),
+    }
+}
+
+/// Ticks every particle and removes the ones that collided along the way,
+/// along with their entries in `ids`. Returns whether there were any.
+///
+/// Fails with the position in `particles` of the first one that overflows.
+fn tick_all<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+    ids: &mut Vec<usize>,
+    prev: &mut Vec<[i64; D]>,
+) -> Result<bool, usize> {
+    prev.clear();
+    prev.extend(particles.iter().map(|particle| particle.pos));
+    for (i, particle) in particles.iter_mut().enumerate() {
+        particle.tick().ok_or(i)?;
+    }
+
+    // Sweep and prune: only particles whose paths overlap along the first
+    // axis can possibly meet.
+    let extent = |i: usize| {
+        let (a, b) = (prev[i][0], particles[i].pos[0]);
+        (a.min(b), a.max(b))
+    };
+    let mut order = (0..particles.len()).collect::<Vec<_>>();
+    order.sort_unstable_by_key(|&i| extent(i).0);
+
+    let mut events = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for &i in &order {
+        let (lo, _) = extent(i);
+        active.retain(|&j| extent(j).1 >= lo);
+
+        for &j in &active {
+            let path = |k: usize| [&prev[k], &particles[k].pos];
+            if let Some(moment) = meeting(path(i), path(j)) {
+                events.push((moment, i, j));
+            }
+        }
+
+        active.push(i);
+    }
+    events.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut destroyed: Vec<Option<Moment>> = vec![None; particles.len()];
+    for (moment, i, j) in events {
+        let alive = |k: usize| destroyed[k].is_none_or(|then| then.cmp(moment) == Ordering::Equal);
+
+        if alive(i) && alive(j) {
+            destroyed[i] = Some(moment);
+            destroyed[j] = Some(moment);
+        }
+    }
+
+    let alive = destroyed.iter().map(Option::is_none).collect::<Vec<_>>();
+    retain_alive(ids, &alive);
+    retain_alive(particles, &alive);
+    Ok(ids.len() < alive.len())
+}
+
+pub(crate) fn simulate<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+) -> Result<i64, ParticleError> {
+    let mut ids = (0..particles.len()).collect::<Vec<_>>();
+    let mut prev = Vec::with_capacity(particles.len());
+    let mut prover = Prover::new();
+
+    // The proof carries over: gaps that only ever grow from one tick to the
+    // next stay open in between too.
+    until_settled(
+        particles,
+        |particles| prover.particles(particles),
+        |particles| tick_all(particles, &mut ids, &mut prev).map_err(|i| ids[i]),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{particle, solve_part2_with_model, CollisionModel};
+
+    fn survivors<const D: usize>(particles: &[Particle<D>], model: CollisionModel) -> usize {
+        solve_part2_with_model(&mut particles.to_vec(), model).unwrap()
+    }
+
+    #[test]
+    fn swapping_places_collides() {
+        let particles = [particle([0], [1], [0]), particle([1], [-1], [0])];
+        assert_eq!(survivors(&particles, CollisionModel::Discrete), 2);
+        assert_eq!(survivors(&particles, CollisionModel::Continuous), 0);
+    }
+
+    #[test]
+    fn crossing_paths_collide() {
+        // Both pass through (1, 1) halfway through the first tick.
+        let particles = [
+            particle([0, 0], [2, 2], [0, 0]),
+            particle([2, 0], [-2, 2], [0, 0]),
+        ];
+        assert_eq!(survivors(&particles, CollisionModel::Discrete), 2);
+        assert_eq!(survivors(&particles, CollisionModel::Continuous), 0);
+
+        // The same paths a tick apart cross the same point, but never at the
+        // same moment.
+        let particles = [
+            particle([0, 0], [2, 2], [0, 0]),
+            particle([4, -2], [-2, 2], [0, 0]),
+        ];
+        assert_eq!(survivors(&particles, CollisionModel::Continuous), 2);
+    }
+
+    #[test]
+    fn meeting_at_the_end_of_a_tick_counts() {
+        let particles = [
+            particle([0], [2], [0]),
+            particle([4], [-2], [0]),
+            particle([100], [0], [0]),
+        ];
+        assert_eq!(survivors(&particles, CollisionModel::Discrete), 1);
+        assert_eq!(survivors(&particles, CollisionModel::Continuous), 1);
+
+        let [a, b] = [[0], [2]];
+        let [c, d] = [[4], [2]];
+        let moment = meeting([&a, &b], [&c, &d]).unwrap();
+        assert_eq!((moment.num, moment.den), (4, 4));
+    }
+
+    #[test]
+    fn moments_compare_without_overflowing() {
+        let big = 1 << 100;
+        let earlier = Moment {
+            num: big - 1,
+            den: big,
+        };
+        let later = Moment {
+            num: big,
+            den: big + 1,
+        };
+
+        assert_eq!(earlier.cmp(later), Ordering::Less);
+        assert_eq!(later.cmp(earlier), Ordering::Greater);
+        assert_eq!(
+            later.cmp(Moment {
+                num: 3 * big,
+                den: 3 * big + 3
+            }),
+            Ordering::Equal
+        );
+    }
+}
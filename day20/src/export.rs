@@ -0,0 +1,231 @@
+//! Snapshots of the swarm over time, written out as CSV or as SVG frames.
+//!
+//! The snapshots come from the same tick-and-bucket step as
+//! `Engine::HashMap`, so whatever is drawn is what `solve_part2` counted.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use crate::{parse::AXES, tick_all, Particle};
+
+/// Where one particle is on a given tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot<const D: usize = 3> {
+    /// The particle's index in the original input.
+    pub id: usize,
+    pub pos: [i64; D],
+    /// `false` only on the tick the particle is destroyed; it doesn't appear
+    /// in any later frame.
+    pub alive: bool,
+}
+
+/// Every particle still around on a given tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<const D: usize = 3> {
+    pub tick: i64,
+    pub particles: Vec<Snapshot<D>>,
+}
+
+/// The sequence of frames starting with the initial positions at tick 0. It
+/// only ends if a particle gets too far away for an `i64`, just before the
+/// frame where that happens.
+#[derive(Debug, Clone)]
+pub struct Trajectory<const D: usize = 3> {
+    particles: Vec<Particle<D>>,
+    ids: Vec<usize>,
+    tick: i64,
+    positions: HashMap<[i64; D], usize>,
+    overflowed: bool,
+}
+
+impl<const D: usize> Trajectory<D> {
+    pub fn new(particles: &[Particle<D>]) -> Self {
+        Self {
+            particles: particles.to_vec(),
+            ids: (0..particles.len()).collect(),
+            tick: 0,
+            positions: HashMap::new(),
+            overflowed: false,
+        }
+    }
+}
+
+impl<const D: usize> Iterator for Trajectory<D> {
+    type Item = Frame<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.overflowed {
+            return None;
+        }
+
+        // Particles are only ever destroyed after a tick, never at the start.
+        if self.tick != 0 && tick_all(&mut self.particles, &mut self.positions).is_err() {
+            self.overflowed = true;
+            return None;
+        }
+
+        let particles = self
+            .ids
+            .iter()
+            .zip(&self.particles)
+            .map(|(&id, particle)| Snapshot {
+                id,
+                pos: particle.pos,
+                alive: self.tick == 0 || self.positions[&particle.pos] == 1,
+            })
+            .collect::<Vec<_>>();
+
+        let mut alive = particles.iter().map(|snapshot| snapshot.alive);
+        self.particles.retain(|_| alive.next().unwrap());
+        self.ids = particles
+            .iter()
+            .filter(|snapshot| snapshot.alive)
+            .map(|snapshot| snapshot.id)
+            .collect();
+
+        let frame = Frame {
+            tick: self.tick,
+            particles,
+        };
+        self.tick += 1;
+        Some(frame)
+    }
+}
+
+fn axis_name(axis: usize) -> String {
+    match AXES.get(axis) {
+        Some(name) => name.to_string(),
+        None => format!("axis{}", axis),
+    }
+}
+
+/// Writes `frames` as CSV with a `tick,id,x,y,z,alive` header, one row per
+/// particle per frame.
+pub fn write_csv<const D: usize>(
+    mut out: impl Write,
+    frames: impl IntoIterator<Item = Frame<D>>,
+) -> io::Result<()> {
+    write!(out, "tick,id")?;
+    for axis in 0..D {
+        write!(out, ",{}", axis_name(axis))?;
+    }
+    writeln!(out, ",alive")?;
+
+    for frame in frames {
+        for snapshot in &frame.particles {
+            write!(out, "{},{}", frame.tick, snapshot.id)?;
+            for x in &snapshot.pos {
+                write!(out, ",{}", x)?;
+            }
+            writeln!(out, ",{}", snapshot.alive)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders each frame as a standalone SVG document, looking down on the plane
+/// spanned by `axes` with the first axis pointing right and the second up.
+/// All frames share one viewport, which always contains the origin, so they
+/// can be played back as an animation. Particles are drawn red on the tick
+/// they're destroyed.
+pub fn svg_frames<const D: usize>(frames: &[Frame<D>], axes: [usize; 2]) -> Vec<String> {
+    let [h, v] = axes;
+    assert!(h < D && v < D, "axes {:?} out of range for {}D", axes, D);
+
+    let project = |pos: &[i64; D]| (pos[h], -pos[v]);
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (0, 0, 0, 0);
+    for snapshot in frames.iter().flat_map(|frame| &frame.particles) {
+        let (x, y) = project(&snapshot.pos);
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+
+    let size = (max_x - min_x).max(max_y - min_y).max(1);
+    let radius = (size / 200).max(1);
+    let pad = 2 * radius;
+
+    frames
+        .iter()
+        .map(|frame| {
+            let mut svg = String::new();
+            writeln!(
+                svg,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+                min_x - pad,
+                min_y - pad,
+                max_x - min_x + 2 * pad,
+                max_y - min_y + 2 * pad,
+            )
+            .unwrap();
+            writeln!(svg, "<title>tick {}</title>", frame.tick).unwrap();
+
+            for snapshot in &frame.particles {
+                let (x, y) = project(&snapshot.pos);
+                let (r, fill) = if snapshot.alive {
+                    (radius, "black")
+                } else {
+                    (2 * radius, "red")
+                };
+                writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"><title>{}</title></circle>"#,
+                    x, y, r, fill, snapshot.id
+                )
+                .unwrap();
+            }
+
+            svg.push_str("</svg>\n");
+            svg
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::particle;
+
+    fn csv<const D: usize>(particles: &[Particle<D>], frames: usize) -> String {
+        let mut out = Vec::new();
+        write_csv(&mut out, Trajectory::new(particles).take(frames)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn destroyed_particles_show_up_once_more() {
+        let particles = [
+            particle([-2, 0, 0], [1, 0, 0], [0, 0, 0]),
+            particle([2, 0, 0], [-1, 0, 0], [0, 0, 0]),
+            particle([0, 5, 0], [0, 0, 0], [0, 0, 0]),
+        ];
+
+        assert_eq!(
+            csv(&particles, 4),
+            "\
+tick,id,x,y,z,alive
+0,0,-2,0,0,true
+0,1,2,0,0,true
+0,2,0,5,0,true
+1,0,-1,0,0,true
+1,1,1,0,0,true
+1,2,0,5,0,true
+2,0,0,0,0,false
+2,1,0,0,0,false
+2,2,0,5,0,true
+3,2,0,5,0,true
+"
+        );
+    }
+
+    #[test]
+    fn header_names_every_axis() {
+        let particles = [particle([1, 2], [0, 0], [0, 0])];
+        assert_eq!(csv(&particles, 1), "tick,id,x,y,alive\n0,0,1,2,true\n");
+    }
+}
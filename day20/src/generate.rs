@@ -0,0 +1,294 @@
+//! Deterministic puzzle-like inputs for fuzzing and benchmarking, optionally
+//! with collisions planted at chosen ticks.
+
+use std::ops::RangeInclusive;
+
+use crate::{analytic::collision_tick, Particle};
+
+/// SplitMix64, which is tiny and gives the same stream on every platform.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        // Bias is irrelevant next to 2^64 for the ranges used here.
+        self.next_u64() % n
+    }
+
+    fn in_range(&mut self, range: &RangeInclusive<i64>) -> i64 {
+        let (lo, hi) = (*range.start(), *range.end());
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        (lo as i128 + (u128::from(self.next_u64()) % span) as i128) as i64
+    }
+
+    fn vector<const D: usize>(&mut self, range: &RangeInclusive<i64>) -> [i64; D] {
+        let mut vector = [0; D];
+        vector.iter_mut().for_each(|x| *x = self.in_range(range));
+        vector
+    }
+}
+
+/// How many draws a particle gets before the ranges are deemed too narrow to
+/// fit it in.
+const MAX_ATTEMPTS: usize = 10_000;
+
+/// The particles generated so far, along with the tick each one is planned to
+/// be destroyed on, or `i64::MAX` if it should survive.
+struct Swarm<const D: usize> {
+    particles: Vec<Particle<D>>,
+    deaths: Vec<i64>,
+}
+
+impl<const D: usize> Swarm<D> {
+    /// Whether `particle`, destroyed on tick `death`, never meets any particle
+    /// already in the swarm while both are still meant to be around. Pairs too
+    /// extreme to solve for are turned away too.
+    fn admits(&self, particle: &Particle<D>, death: i64) -> bool {
+        self.particles
+            .iter()
+            .zip(&self.deaths)
+            .all(|(other, &other_death)| {
+                collision_tick(particle, other)
+                    .is_ok_and(|t| t.is_none_or(|t| t > death.min(other_death)))
+            })
+    }
+}
+
+/// Generates particles in the `p=<..>, v=<..>, a=<..>` format from a seed.
+///
+/// Apart from the planted collisions, no two particles ever collide: each
+/// particle is drawn again until it never meets any other one, or only once
+/// one of them was already meant to be destroyed. Checking that against every
+/// earlier particle makes generation quadratic in the particle count.
+///
+/// Panics if the ranges are too narrow to fit every particle in this way.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    seed: u64,
+    count: usize,
+    pos: RangeInclusive<i64>,
+    vel: RangeInclusive<i64>,
+    accel: RangeInclusive<i64>,
+    collisions: Vec<(i64, usize)>,
+}
+
+impl Generator {
+    /// A generator shaped like the puzzle input: a thousand particles with
+    /// coordinates up to ±10000, velocities up to ±200 and accelerations up
+    /// to ±20.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            count: 1000,
+            pos: -10_000..=10_000,
+            vel: -200..=200,
+            accel: -20..=20,
+            collisions: Vec::new(),
+        }
+    }
+
+    /// How many particles to generate in total, planted ones included.
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// The range initial positions are drawn from. Particles planted to
+    /// collide may start outside it, as their meeting point is what's drawn.
+    pub fn pos_range(mut self, range: RangeInclusive<i64>) -> Self {
+        assert!(!range.is_empty(), "empty position range");
+        self.pos = range;
+        self
+    }
+
+    pub fn vel_range(mut self, range: RangeInclusive<i64>) -> Self {
+        assert!(!range.is_empty(), "empty velocity range");
+        self.vel = range;
+        self
+    }
+
+    pub fn accel_range(mut self, range: RangeInclusive<i64>) -> Self {
+        assert!(!range.is_empty(), "empty acceleration range");
+        self.accel = range;
+        self
+    }
+
+    /// Plants `size` particles that all meet at one point after `tick` ticks,
+    /// and not before.
+    pub fn collision(mut self, tick: i64, size: usize) -> Self {
+        assert!(tick >= 1, "collisions can only happen from tick 1 onwards");
+        assert!(size >= 2, "a collision needs at least two particles");
+        self.collisions.push((tick, size));
+        self
+    }
+
+    /// How many particles `solve_part2` will leave.
+    pub fn expected_survivors(&self) -> usize {
+        self.count - self.planted()
+    }
+
+    fn planted(&self) -> usize {
+        self.collisions.iter().map(|&(_, size)| size).sum()
+    }
+
+    pub fn generate<const D: usize>(&self) -> Vec<Particle<D>> {
+        let planted = self.planted();
+        assert!(
+            planted <= self.count,
+            "{} planted particles don't fit in {}",
+            planted,
+            self.count
+        );
+
+        let mut rng = Rng(self.seed);
+        let mut swarm = Swarm {
+            particles: Vec::with_capacity(self.count),
+            deaths: Vec::with_capacity(self.count),
+        };
+
+        for &(tick, size) in &self.collisions {
+            let meeting = rng.vector::<D>(&self.pos);
+            let mut group: Vec<Particle<D>> = Vec::with_capacity(size);
+
+            let mut attempts = 0;
+            while group.len() < size {
+                attempts += 1;
+                assert!(
+                    attempts <= MAX_ATTEMPTS * size,
+                    "couldn't plant {} particles meeting at tick {}, try wider ranges",
+                    size,
+                    tick
+                );
+
+                let mut particle = Particle {
+                    pos: meeting,
+                    vel: rng.vector(&self.vel),
+                    accel: rng.vector(&self.accel),
+                };
+                for axis in 0..D {
+                    let (v, a) = (particle.vel[axis], particle.accel[axis]);
+                    particle.pos[axis] = v
+                        .checked_mul(tick)
+                        .zip(a.checked_mul(tick * (tick + 1) / 2))
+                        .and_then(|(v, a)| particle.pos[axis].checked_sub(v)?.checked_sub(a))
+                        .expect("planted collision is too late for an i64 start");
+                }
+
+                // Two members whose paths also cross earlier would break off
+                // from the group before `tick`.
+                if group
+                    .iter()
+                    .all(|other| collision_tick(&particle, other) == Ok(Some(tick)))
+                    && swarm.admits(&particle, tick)
+                {
+                    group.push(particle);
+                }
+            }
+
+            swarm.deaths.extend(group.iter().map(|_| tick));
+            swarm.particles.extend(group);
+        }
+
+        while swarm.particles.len() < self.count {
+            let particle = (0..MAX_ATTEMPTS)
+                .map(|_| Particle {
+                    pos: rng.vector(&self.pos),
+                    vel: rng.vector(&self.vel),
+                    accel: rng.vector(&self.accel),
+                })
+                .find(|particle| swarm.admits(particle, i64::MAX))
+                .unwrap_or_else(|| {
+                    panic!(
+                        "couldn't fit {} particles that never collide, try wider ranges",
+                        self.count - planted
+                    )
+                });
+
+            swarm.particles.push(particle);
+            swarm.deaths.push(i64::MAX);
+        }
+
+        let mut particles = swarm.particles;
+
+        // Fisher-Yates, so planted particles don't all sit at the front.
+        for i in (1..particles.len()).rev() {
+            particles.swap(i, rng.below(i as u64 + 1) as usize);
+        }
+
+        particles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solve_part2_with, Engine};
+
+    #[test]
+    fn same_seed_same_particles() {
+        let generator = Generator::new(7).count(100);
+        assert_eq!(generator.generate::<3>(), generator.generate::<3>());
+        assert_ne!(
+            generator.generate::<3>(),
+            Generator::new(8).count(100).generate::<3>()
+        );
+    }
+
+    #[test]
+    fn planted_collisions_leave_expected_survivors() {
+        let generator = Generator::new(2017)
+            .count(300)
+            .pos_range(-100..=100)
+            .vel_range(-5..=5)
+            .accel_range(-2..=2)
+            .collision(1, 2)
+            .collision(10, 3)
+            .collision(10, 2)
+            .collision(50, 4);
+        let particles = generator.generate::<3>();
+        assert_eq!(generator.expected_survivors(), 289);
+
+        for &engine in &[Engine::HashMap, Engine::Analytic] {
+            assert_eq!(
+                solve_part2_with(&mut particles.clone(), engine),
+                Ok(generator.expected_survivors())
+            );
+        }
+    }
+
+    #[test]
+    fn values_stay_in_range() {
+        let particles = Generator::new(1)
+            .count(500)
+            .pos_range(-50..=50)
+            .vel_range(-3..=4)
+            .accel_range(1..=2)
+            .generate::<2>();
+
+        for particle in &particles {
+            assert!(particle.pos.iter().all(|x| (-50..=50).contains(x)));
+            assert!(particle.vel.iter().all(|x| (-3..=4).contains(x)));
+            assert!(particle.accel.iter().all(|x| (1..=2).contains(x)));
+        }
+        assert!(particles.iter().any(|particle| particle.accel.contains(&2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "try wider ranges")]
+    fn narrow_ranges_fail_loudly() {
+        Generator::new(0)
+            .count(10)
+            .pos_range(0..=1)
+            .vel_range(0..=0)
+            .accel_range(0..=0)
+            .generate::<1>();
+    }
+}
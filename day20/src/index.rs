@@ -0,0 +1,294 @@
+//! A k-d tree over the swarm's positions at a given tick, for nearest
+//! neighbour and radius queries.
+
+use std::collections::BinaryHeap;
+
+use crate::{CollisionEvents, Metric, Particle, ParticleError};
+
+/// The swarm frozen at one tick, indexed for spatial queries.
+///
+/// Queries accept any `Metric` whose distance never shrinks as the difference
+/// along an axis grows, which all the provided ones satisfy. Distances too
+/// large for an `i128` are treated as infinitely far.
+#[derive(Debug, Clone)]
+pub struct SwarmIndex<const D: usize = 3> {
+    /// An implicit k-d tree: each slice's middle element splits the rest of
+    /// it along axis `depth % D`.
+    nodes: Vec<(usize, [i128; D])>,
+}
+
+impl<const D: usize> SwarmIndex<D> {
+    /// Indexes every particle at its position after `t` ticks, collisions or
+    /// not.
+    pub fn at(particles: &[Particle<D>], t: i64) -> Result<Self, ParticleError> {
+        Self::build(particles, t, &vec![false; particles.len()])
+    }
+
+    /// Indexes the particles still around after `t` ticks, leaving out the
+    /// ones destroyed by a collision on or before tick `t`.
+    pub fn survivors_at(particles: &[Particle<D>], t: i64) -> Result<Self, ParticleError> {
+        let mut destroyed = vec![false; particles.len()];
+        for (_, _, pile) in CollisionEvents::new(particles)?.take_while(|event| event.0 <= t) {
+            pile.into_iter().for_each(|i| destroyed[i] = true);
+        }
+
+        Self::build(particles, t, &destroyed)
+    }
+
+    fn build(particles: &[Particle<D>], t: i64, skip: &[bool]) -> Result<Self, ParticleError> {
+        let mut nodes = Vec::with_capacity(particles.len());
+        for (index, particle) in particles.iter().enumerate() {
+            if !skip[index] {
+                let pos = particle
+                    .checked_pos_at(t)
+                    .ok_or(ParticleError::Overflow { index, t })?;
+                nodes.push((index, pos));
+            }
+        }
+
+        fn split<const D: usize>(nodes: &mut [(usize, [i128; D])], depth: usize) {
+            if nodes.len() > 1 {
+                let mid = nodes.len() / 2;
+                nodes.select_nth_unstable_by_key(mid, |node| node.1[depth % D]);
+
+                let (left, right) = nodes.split_at_mut(mid);
+                split(left, depth + 1);
+                split(&mut right[1..], depth + 1);
+            }
+        }
+        split(&mut nodes, 0);
+
+        Ok(Self { nodes })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The `k` particles closest to `point`, as `(index, distance)` pairs
+    /// sorted by distance and then by index.
+    pub fn nearest<M: Metric>(&self, metric: &M, point: &[i64; D], k: usize) -> Vec<(usize, i128)> {
+        let point = point.map(i128::from);
+        let mut best = BinaryHeap::with_capacity(k + 1);
+
+        if k != 0 {
+            nearest(&self.nodes, 0, metric, &point, k, &mut best);
+        }
+
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|(dist, index)| (index, dist))
+            .collect()
+    }
+
+    /// Every particle within `radius` of `center`, inclusive, in index order.
+    pub fn within<M: Metric>(&self, metric: &M, center: &[i64; D], radius: i128) -> Vec<usize> {
+        let center = center.map(i128::from);
+        let mut found = Vec::new();
+
+        within(&self.nodes, 0, metric, &center, radius, &mut found);
+
+        found.sort_unstable();
+        found
+    }
+}
+
+fn dist<M: Metric, const D: usize>(metric: &M, a: &[i128; D], b: &[i128; D]) -> i128 {
+    let mut delta = [0; D];
+    for ((d, x), y) in delta.iter_mut().zip(a).zip(b) {
+        match x.checked_sub(*y) {
+            Some(diff) => *d = diff,
+            None => return i128::MAX,
+        }
+    }
+    metric.norm(&delta).unwrap_or(i128::MAX)
+}
+
+/// The distance from `point` to the splitting plane of `axis` at `at`, which
+/// bounds the distance to anything on the plane's other side.
+fn plane_dist<M: Metric, const D: usize>(
+    metric: &M,
+    point: &[i128; D],
+    axis: usize,
+    at: i128,
+) -> i128 {
+    let mut on_plane = *point;
+    on_plane[axis] = at;
+    dist(metric, point, &on_plane)
+}
+
+fn nearest<M: Metric, const D: usize>(
+    nodes: &[(usize, [i128; D])],
+    depth: usize,
+    metric: &M,
+    point: &[i128; D],
+    k: usize,
+    best: &mut BinaryHeap<(i128, usize)>,
+) {
+    if nodes.is_empty() {
+        return;
+    }
+
+    let mid = nodes.len() / 2;
+    let (index, pos) = nodes[mid];
+    best.push((dist(metric, point, &pos), index));
+    if best.len() > k {
+        best.pop();
+    }
+
+    let axis = depth % D;
+    let (left, right) = (&nodes[..mid], &nodes[mid + 1..]);
+    let (near, far) = if point[axis] < pos[axis] {
+        (left, right)
+    } else {
+        (right, left)
+    };
+
+    nearest(near, depth + 1, metric, point, k, best);
+
+    // Equal distances still need a look, as a lower index would win the tie.
+    let bound = plane_dist(metric, point, axis, pos[axis]);
+    if best.len() < k || best.peek().is_some_and(|&(worst, _)| bound <= worst) {
+        nearest(far, depth + 1, metric, point, k, best);
+    }
+}
+
+fn within<M: Metric, const D: usize>(
+    nodes: &[(usize, [i128; D])],
+    depth: usize,
+    metric: &M,
+    center: &[i128; D],
+    radius: i128,
+    found: &mut Vec<usize>,
+) {
+    if nodes.is_empty() {
+        return;
+    }
+
+    let mid = nodes.len() / 2;
+    let (index, pos) = nodes[mid];
+    if dist(metric, center, &pos) <= radius {
+        found.push(index);
+    }
+
+    let axis = depth % D;
+    let (left, right) = (&nodes[..mid], &nodes[mid + 1..]);
+    let reaches_plane = plane_dist(metric, center, axis, pos[axis]) <= radius;
+
+    if center[axis] < pos[axis] || reaches_plane {
+        within(left, depth + 1, metric, center, radius, found);
+    }
+    if center[axis] >= pos[axis] || reaches_plane {
+        within(right, depth + 1, metric, center, radius, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::{Chebyshev, Generator, Manhattan, Simulation};
+
+    /// Plenty of particles at the same distances, so ties get broken often.
+    fn swarm() -> Vec<Particle<2>> {
+        Generator::new(14)
+            .count(200)
+            .pos_range(-20..=20)
+            .vel_range(-3..=3)
+            .accel_range(-1..=1)
+            .collision(2, 3)
+            .collision(5, 2)
+            .generate()
+    }
+
+    /// Every particle not in `skip` with its distance from `point` after `t`
+    /// ticks, nearest first and then by index.
+    fn brute_force<M: Metric>(
+        particles: &[Particle<2>],
+        skip: &BTreeSet<usize>,
+        metric: &M,
+        point: &[i64; 2],
+        t: i64,
+    ) -> Vec<(usize, i128)> {
+        let mut all = particles
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !skip.contains(index))
+            .map(|(index, particle)| {
+                let pos = particle.pos_at(t);
+                let delta = [pos[0] - i128::from(point[0]), pos[1] - i128::from(point[1])];
+                (index, metric.norm(&delta).unwrap())
+            })
+            .collect::<Vec<_>>();
+        all.sort_unstable_by_key(|&(index, dist)| (dist, index));
+        all
+    }
+
+    fn assert_matches<M: Metric>(
+        index: &SwarmIndex<2>,
+        expected: &[(usize, i128)],
+        metric: &M,
+        point: &[i64; 2],
+    ) {
+        for &k in &[0, 1, 5, 17, 1000] {
+            let nearest = &expected[..k.min(expected.len())];
+            assert_eq!(index.nearest(metric, point, k), nearest, "k = {}", k);
+        }
+
+        for &radius in &[0, 3, 10, 40, 1000] {
+            let mut within = expected
+                .iter()
+                .filter(|&&(_, dist)| dist <= radius)
+                .map(|&(index, _)| index)
+                .collect::<Vec<_>>();
+            within.sort_unstable();
+            assert_eq!(
+                index.within(metric, point, radius),
+                within,
+                "radius = {}",
+                radius
+            );
+        }
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let particles = swarm();
+        let none = BTreeSet::new();
+
+        for &t in &[0, 3, 10] {
+            let index = SwarmIndex::at(&particles, t).unwrap();
+
+            for point in &[[0, 0], [7, -3], [100, 100]] {
+                let expected = brute_force(&particles, &none, &Manhattan, point, t);
+                assert_matches(&index, &expected, &Manhattan, point);
+
+                let expected = brute_force(&particles, &none, &Chebyshev, point, t);
+                assert_matches(&index, &expected, &Chebyshev, point);
+            }
+        }
+    }
+
+    #[test]
+    fn survivors_at_leaves_out_destroyed_particles() {
+        let particles = swarm();
+        let mut simulation = Simulation::new(particles.clone());
+
+        for t in 1..=6 {
+            simulation.step().unwrap();
+            let index = SwarmIndex::survivors_at(&particles, t).unwrap();
+            assert_eq!(index.len(), simulation.survivors());
+
+            let point = [1, 2];
+            let expected = brute_force(&particles, simulation.destroyed(), &Manhattan, &point, t);
+            assert_matches(&index, &expected, &Manhattan, &point);
+        }
+
+        assert_eq!(simulation.survivors(), particles.len() - 5);
+    }
+}
@@ -0,0 +1,186 @@
+//! Closed-form positions that never silently wrap: they're computed in `i64`
+//! when that's enough, in `i128` when it isn't, and reported as an error when
+//! even that overflows.
+
+use std::{error::Error, fmt};
+
+use crate::{Manhattan, Metric, Particle};
+
+/// Why a question about the swarm couldn't be answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleError {
+    /// The position of particle `index` after `t` ticks, or its distance from
+    /// the reference point, doesn't fit in an `i128`.
+    Overflow { index: usize, t: i64 },
+    /// Working out when particles `index` and `other` meet overflows an
+    /// `i128`.
+    PairOverflow { index: usize, other: usize },
+    /// The polynomial ranking particle `index` in the long run has a
+    /// coefficient that doesn't fit in an `i128`.
+    LongRunOverflow { index: usize },
+}
+
+impl fmt::Display for ParticleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParticleError::Overflow { index, t } => write!(
+                f,
+                "particle {} at t={} is too far away for an i128",
+                index, t
+            ),
+            ParticleError::PairOverflow { index, other } => write!(
+                f,
+                "particles {} and {} are too far apart to tell when they meet",
+                index, other
+            ),
+            ParticleError::LongRunOverflow { index } => write!(
+                f,
+                "particle {} is too far away to rank in the long run",
+                index
+            ),
+        }
+    }
+}
+
+impl Error for ParticleError {}
+
+fn narrow_axis_at(p: i64, v: i64, a: i64, t: i64) -> Option<i64> {
+    let steps = t.checked_mul(t.checked_add(1)?)? / 2;
+    a.checked_mul(steps)?
+        .checked_add(v.checked_mul(t)?)?
+        .checked_add(p)
+}
+
+fn wide_axis_at(p: i64, v: i64, a: i64, t: i64) -> Option<i128> {
+    let (p, v, a, t) = (i128::from(p), i128::from(v), i128::from(a), i128::from(t));
+    let steps = t.checked_mul(t.checked_add(1)?)? / 2;
+    a.checked_mul(steps)?
+        .checked_add(v.checked_mul(t)?)?
+        .checked_add(p)
+}
+
+impl<const D: usize> Particle<D> {
+    /// Like `pos_at`, but falls back to `i128` arithmetic for axes that
+    /// overflow an `i64` and returns `None` if even that overflows.
+    pub fn checked_pos_at(&self, t: i64) -> Option<[i128; D]> {
+        let mut pos = [0; D];
+
+        for (axis, x) in pos.iter_mut().enumerate() {
+            let (p, v, a) = (self.pos[axis], self.vel[axis], self.accel[axis]);
+            *x = match narrow_axis_at(p, v, a, t) {
+                Some(x) => x.into(),
+                None => wide_axis_at(p, v, a, t)?,
+            };
+        }
+
+        Some(pos)
+    }
+
+    /// Like `dist_at`, with the same overflow handling as `checked_pos_at`.
+    pub fn checked_dist_at(&self, t: i64) -> Option<i128> {
+        Manhattan.norm(&self.checked_pos_at(t)?)
+    }
+
+    /// The position after `t` calls to `tick`: velocity is updated before
+    /// position, so acceleration contributes `a * t * (t + 1) / 2`.
+    ///
+    /// Panics if the position doesn't fit even in an `i128`; see
+    /// `checked_pos_at`.
+    pub fn pos_at(&self, t: i64) -> [i128; D] {
+        self.checked_pos_at(t).expect("position overflows an i128")
+    }
+
+    /// The Manhattan distance from the origin after `t` ticks.
+    ///
+    /// Panics if the distance doesn't fit even in an `i128`; see
+    /// `checked_dist_at`.
+    pub fn dist_at(&self, t: i64) -> i128 {
+        self.checked_dist_at(t).expect("distance overflows an i128")
+    }
+}
+
+/// The particle closest to `origin` under `metric` after `t` ticks, ignoring
+/// collisions. The lowest index wins ties.
+pub fn closest_particle<M: Metric, const D: usize>(
+    particles: &[Particle<D>],
+    metric: &M,
+    origin: &[i64; D],
+    t: i64,
+) -> Result<Option<usize>, ParticleError> {
+    let mut closest: Option<(i128, usize)> = None;
+
+    for (index, particle) in particles.iter().enumerate() {
+        let dist = particle
+            .checked_pos_at(t)
+            .and_then(|pos| {
+                let mut delta = [0; D];
+                for ((d, x), &o) in delta.iter_mut().zip(&pos).zip(origin) {
+                    *d = x.checked_sub(o.into())?;
+                }
+                metric.norm(&delta)
+            })
+            .ok_or(ParticleError::Overflow { index, t })?;
+
+        if closest.is_none_or(|(best, _)| dist < best) {
+            closest = Some((dist, index));
+        }
+    }
+
+    Ok(closest.map(|(_, index)| index))
+}
+
+/// The particle closest to the origin by Manhattan distance after `t` ticks,
+/// ignoring collisions. The lowest index wins ties.
+pub fn closest_at<const D: usize>(
+    particles: &[Particle<D>],
+    t: i64,
+) -> Result<Option<usize>, ParticleError> {
+    closest_particle(particles, &Manhattan, &[0; D], t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::particle;
+    use crate::{solve_part2_with, solve_part2_with_model, CollisionModel, Engine, Simulation};
+
+    #[test]
+    fn positions_widen_past_i64() {
+        let particle = particle([i64::MAX], [i64::MAX], [i64::MAX]);
+        let max = i128::from(i64::MAX);
+
+        assert_eq!(particle.pos_at(2), [6 * max]);
+        assert_eq!(particle.dist_at(2), 6 * max);
+        assert_eq!(particle.checked_pos_at(i64::MAX), None);
+    }
+
+    #[test]
+    fn simulations_report_overflow() {
+        // The first two keep the swarm from settling until the third one has
+        // flown off the end of the axis on tick 3.
+        let swarm = vec![
+            particle([0], [0], [0]),
+            particle([100], [-1], [0]),
+            particle([i64::MAX - 10], [5], [0]),
+        ];
+        let overflow = Err(ParticleError::Overflow { index: 2, t: 3 });
+
+        let mut engines = vec![Engine::HashMap, Engine::SortSweep];
+        #[cfg(feature = "parallel")]
+        engines.push(Engine::Parallel);
+        for engine in engines {
+            assert_eq!(
+                solve_part2_with(&mut swarm.clone(), engine),
+                overflow,
+                "{:?}",
+                engine
+            );
+        }
+
+        assert_eq!(
+            solve_part2_with_model(&mut swarm.clone(), CollisionModel::Continuous),
+            overflow
+        );
+        assert_eq!(Simulation::new(swarm).run(), overflow);
+    }
+}
@@ -1,114 +1,280 @@
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy)]
-pub struct Particle {
-    pos: [i64; 3],
-    vel: [i64; 3],
-    accel: [i64; 3],
-}
-
-fn parse_triplet(var: &str) -> [i64; 3] {
-    let var = &var["x=<".len()..var.len() - ">".len()];
-    let mut parts = var.splitn(3, ',').map(|n| n.parse().unwrap());
-    [
-        parts.next().unwrap(),
-        parts.next().unwrap(),
-        parts.next().unwrap(),
-    ]
-}
-
-impl Particle {
-    fn from_input(line: &str) -> Self {
-        let mut parts = line.splitn(3, ", ");
-
-        Self {
-            pos: parse_triplet(parts.next().unwrap()),
-            vel: parse_triplet(parts.next().unwrap()),
-            accel: parse_triplet(parts.next().unwrap()),
+mod analytic;
+mod continuous;
+mod export;
+mod generate;
+mod index;
+mod kinematics;
+mod long_run;
+mod metric;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod parse;
+mod proof;
+mod simulation;
+mod sweep;
+
+pub use analytic::CollisionEvents;
+pub use export::{svg_frames, write_csv, Frame, Snapshot, Trajectory};
+pub use generate::Generator;
+pub use index::SwarmIndex;
+pub use kinematics::{closest_at, closest_particle, ParticleError};
+pub use long_run::{
+    closest_in_long_run, closest_in_long_run_by, cmp_long_run, cmp_long_run_by, Closest,
+};
+pub use metric::{Chebyshev, EuclideanSquared, Manhattan, Metric};
+pub use parse::{
+    load_input_from, parse_input, Component, Field, LoadError, ParseErrorKind, ParseParticleError,
+};
+pub use proof::no_future_collisions;
+
+use proof::{Prover, Schedule};
+pub use simulation::Simulation;
+
+/// A particle moving through `D`-dimensional space. The puzzle itself is 3D.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Particle<const D: usize = 3> {
+    pos: [i64; D],
+    vel: [i64; D],
+    accel: [i64; D],
+}
+
+impl<const D: usize> Particle<D> {
+    /// Advances the particle by one tick, or leaves it as it was and returns
+    /// `None` if its velocity or position would overflow an `i64`.
+    #[must_use]
+    fn tick(&mut self) -> Option<()> {
+        let mut next = *self;
+        for axis in 0..D {
+            next.vel[axis] = self.vel[axis].checked_add(self.accel[axis])?;
+            next.pos[axis] = self.pos[axis].checked_add(next.vel[axis])?;
         }
+
+        *self = next;
+        Some(())
     }
+}
 
-    fn pos_at(&self, t: i64) -> [i64; 3] {
-        let [x, y, z] = self.pos;
-        let [vx, vy, vz] = self.vel;
-        let [ax, ay, az] = self.accel;
+/// Builds a particle for tests, which can't go through the parser for
+/// dimensions other than 3.
+#[cfg(test)]
+pub(crate) fn particle<const D: usize>(
+    pos: [i64; D],
+    vel: [i64; D],
+    accel: [i64; D],
+) -> Particle<D> {
+    Particle { pos, vel, accel }
+}
 
-        [
-            ax * t * t + vx * t + x,
-            ay * t * t + vy * t + y,
-            az * t * t + vz * t + z,
-        ]
+/// The particle that stays closest to the origin in the long run. When
+/// several particles tie, the lowest index among them wins; use
+/// `closest_in_long_run` to tell ties apart.
+///
+/// Panics if `particles` is empty.
+#[inline]
+pub fn solve_part1<const D: usize>(particles: &[Particle<D>]) -> usize {
+    match closest_in_long_run(particles) {
+        Closest::Unique(i) => i,
+        Closest::Tied(tied) => *tied.first().expect("no particles"),
     }
+}
 
-    fn dist_at(&self, t: i64) -> i64 {
-        let [x, y, z] = self.pos_at(t);
-        x.abs() + y.abs() + z.abs()
-    }
+#[inline]
+pub fn load_input() -> Result<Vec<Particle>, ParseParticleError> {
+    parse_input(include_str!("input.txt"))
+}
+
+/// The strategy `solve_part2_with` uses to find collisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Tick the whole swarm, bucketing positions in a `HashMap`, until
+    /// `no_future_collisions` holds.
+    HashMap,
+    /// Solve every pair's trajectories for the exact tick they meet at, which
+    /// is correct no matter how late the last collision happens.
+    Analytic,
+    /// Tick the swarm as a struct of arrays and find collisions by sorting
+    /// positions, stopping under the same rule as `HashMap`.
+    SortSweep,
+    /// Like `SortSweep`, but ticks and sorts across threads with rayon. Gives
+    /// exactly the same result as the sequential engines.
+    #[cfg(feature = "parallel")]
+    Parallel,
+}
 
-    fn tick(&mut self) {
-        let [ax, ay, az] = self.accel;
+/// How `solve_part2_report` left the swarm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Part2Report {
+    /// How many particles are left.
+    pub survivors: usize,
+    /// The tick at which the simulation proved no collision could happen
+    /// anymore, or `None` for `Engine::Analytic`, which doesn't simulate. The
+    /// proof is only attempted every so often, so this can be a little later
+    /// than the first tick it holds on.
+    pub settled_at: Option<i64>,
+}
 
-        let [vx, vy, vz] = &mut self.vel;
-        *vx += ax;
-        *vy += ay;
-        *vz += az;
+/// Removes every particle destroyed in a collision from `particles` and
+/// reports how many are left and when that became certain.
+///
+/// The simulating engines fail if a particle gets too far away for an `i64`
+/// before the swarm settles, leaving `particles` partway through the run. The
+/// analytic one fails as `CollisionEvents::new` does, leaving `particles` as
+/// they were.
+#[inline]
+pub fn solve_part2_report<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+    engine: Engine,
+) -> Result<Part2Report, ParticleError> {
+    let settled_at = match engine {
+        Engine::HashMap => Some(simulate(particles)?),
+        Engine::SortSweep => Some(sweep::simulate(particles)?),
+        #[cfg(feature = "parallel")]
+        Engine::Parallel => Some(parallel::simulate(particles)?),
+        Engine::Analytic => {
+            let mut destroyed = vec![false; particles.len()];
+            for (_, _, pile) in CollisionEvents::new(particles)? {
+                pile.into_iter().for_each(|i| destroyed[i] = true);
+            }
 
-        let [x, y, z] = &mut self.pos;
-        *x += *vx;
-        *y += *vy;
-        *z += *vz;
-    }
+            let mut destroyed = destroyed.into_iter();
+            particles.retain(|_| !destroyed.next().unwrap());
+            None
+        }
+    };
+
+    Ok(Part2Report {
+        survivors: particles.len(),
+        settled_at,
+    })
 }
 
+/// Removes every particle destroyed in a collision from `particles` and
+/// returns how many are left.
 #[inline]
-pub fn solve_part1(particles: &[Particle]) -> usize {
-    particles
-        .iter()
-        .enumerate()
-        .min_by_key(|(_, p)| p.dist_at(100_000))
-        .unwrap()
-        .0
+pub fn solve_part2_with<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+    engine: Engine,
+) -> Result<usize, ParticleError> {
+    Ok(solve_part2_report(particles, engine)?.survivors)
+}
+
+/// What counts as a collision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionModel {
+    /// Particles collide when they share a position right after a tick, as
+    /// in the puzzle.
+    #[default]
+    Discrete,
+    /// Particles move in a straight line from each tick's position to the
+    /// next, and collide if they're at the same point at any moment in
+    /// between, so ones that cross or swap places collide too.
+    Continuous,
 }
 
+/// Like `solve_part2`, under the given collision model.
 #[inline]
-pub fn load_input() -> Vec<Particle> {
-    include_str!("input.txt")
-        .lines()
-        .map(Particle::from_input)
-        .collect::<Vec<_>>()
+pub fn solve_part2_with_model<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+    model: CollisionModel,
+) -> Result<usize, ParticleError> {
+    match model {
+        CollisionModel::Discrete => solve_part2(particles),
+        CollisionModel::Continuous => {
+            continuous::simulate(particles)?;
+            Ok(particles.len())
+        }
+    }
 }
 
 #[inline]
-pub fn solve_part2(particles: &mut Vec<Particle>) -> usize {
-    let mut positions = HashMap::new();
-    let mut prev_len = particles.len();
-    let mut steady = 0;
+pub fn solve_part2<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+) -> Result<usize, ParticleError> {
+    solve_part2_with(particles, Engine::HashMap)
+}
 
-    loop {
-        positions.clear();
+/// Calls `step` to advance `swarm` one tick at a time until `settled` proves
+/// that no collision can happen anymore, and returns how many ticks it took.
+/// The proof is only attempted every so often, see `proof::Schedule`.
+///
+/// `step` returns whether any particles collided, and fails with the input
+/// index of a particle that overflowed.
+fn until_settled<S>(
+    swarm: &mut S,
+    mut settled: impl FnMut(&S) -> bool,
+    mut step: impl FnMut(&mut S) -> Result<bool, usize>,
+) -> Result<i64, ParticleError> {
+    let mut schedule = Schedule::new();
+    let mut tick = 0;
 
-        for particle in particles.iter_mut() {
-            particle.tick();
-            *positions.entry(particle.pos).or_default() += 1;
+    while !(schedule.due(tick) && settled(swarm)) {
+        tick += 1;
+        let collided = step(swarm).map_err(|index| ParticleError::Overflow { index, t: tick })?;
+        if collided {
+            schedule.collided(tick);
         }
+    }
 
-        particles.retain(|particle| positions.get(&particle.pos) == Some(&1));
+    Ok(tick)
+}
 
-        if prev_len == particles.len() {
-            steady += 1;
+/// Ticks every particle and counts how many land on each position. A particle
+/// survives the tick only if its position has a count of one.
+///
+/// Fails with the position in `particles` of the first one that overflows,
+/// having ticked only those before it.
+fn tick_all<const D: usize>(
+    particles: &mut [Particle<D>],
+    positions: &mut HashMap<[i64; D], usize>,
+) -> Result<(), usize> {
+    positions.clear();
 
-            if steady == 10 {
-                break particles.len();
-            }
-        } else {
-            prev_len = particles.len();
-            steady = 0;
-        }
+    for (i, particle) in particles.iter_mut().enumerate() {
+        particle.tick().ok_or(i)?;
+        *positions.entry(particle.pos).or_default() += 1;
     }
+
+    Ok(())
+}
+
+fn simulate<const D: usize>(particles: &mut Vec<Particle<D>>) -> Result<i64, ParticleError> {
+    let mut ids = (0..particles.len()).collect::<Vec<_>>();
+    let mut positions = HashMap::new();
+    let mut alive = Vec::with_capacity(particles.len());
+    let mut prover = Prover::new();
+
+    until_settled(
+        particles,
+        |particles| prover.particles(particles),
+        |particles| {
+            tick_all(particles, &mut positions).map_err(|i| ids[i])?;
+
+            alive.clear();
+            alive.extend(
+                particles
+                    .iter()
+                    .map(|particle| positions[&particle.pos] == 1),
+            );
+            retain_alive(&mut ids, &alive);
+            retain_alive(particles, &alive);
+            Ok(ids.len() < alive.len())
+        },
+    )
+}
+
+/// Keeps the elements of `column` whose counterpart in `alive` is `true`.
+fn retain_alive<T>(column: &mut Vec<T>, alive: &[bool]) {
+    let mut alive = alive.iter();
+    column.retain(|_| *alive.next().unwrap());
 }
 
 #[inline]
 pub fn solve() -> (usize, usize) {
-    let mut particles = load_input();
-    (solve_part1(&particles), solve_part2(&mut particles))
+    let mut particles = load_input().unwrap();
+    (
+        solve_part1(&particles),
+        solve_part2(&mut particles).unwrap(),
+    )
 }
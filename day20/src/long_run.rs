@@ -0,0 +1,127 @@
+//! Exact "closest in the long run" ordering: once every axis has settled on
+//! its final sign, each metric's distance is a plain polynomial in `t` and two
+//! particles can be ranked by its coefficients.
+
+use std::cmp::Ordering;
+
+use crate::{Manhattan, Metric, Particle, ParticleError};
+
+/// Which particles stay closest to a reference point in the long run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Closest {
+    /// Exactly one particle ends up closest.
+    Unique(usize),
+    /// These particles end up at the very same distance on every tick, so no
+    /// one of them is closer than the others. Empty if there are no particles.
+    Tied(Vec<usize>),
+}
+
+impl<const D: usize> Particle<D> {
+    /// Each axis of the offset from `origin` as `[α, β, γ]`, meaning that after
+    /// `t` ticks the offset is `(α*t² + β*t + γ) / 2`.
+    fn offset_polynomials(&self, origin: &[i64; D]) -> [[i128; 3]; D] {
+        let mut axes = [[0; 3]; D];
+
+        for (axis, coeffs) in axes.iter_mut().enumerate() {
+            let p = i128::from(self.pos[axis]) - i128::from(origin[axis]);
+            let v = i128::from(self.vel[axis]);
+            let a = i128::from(self.accel[axis]);
+
+            *coeffs = [a, 2 * v + a, 2 * p];
+        }
+
+        axes
+    }
+}
+
+/// Compares `a` and `b` by their distance from `origin` under `metric` after
+/// enough ticks that the answer will never change again, or returns `None` if
+/// `metric` can't rank one of them without overflowing.
+pub fn cmp_long_run_by<M: Metric, const D: usize>(
+    metric: &M,
+    origin: &[i64; D],
+    a: &Particle<D>,
+    b: &Particle<D>,
+) -> Option<Ordering> {
+    let a = metric.long_run(&a.offset_polynomials(origin))?;
+    let b = metric.long_run(&b.offset_polynomials(origin))?;
+    Some(a.cmp(&b))
+}
+
+/// Compares `a` and `b` by their Manhattan distance from the origin after
+/// enough ticks that the answer will never change again.
+///
+/// Acceleration magnitude is compared first, then velocity, then position,
+/// each taken with the sign its axis eventually settles on.
+pub fn cmp_long_run<const D: usize>(a: &Particle<D>, b: &Particle<D>) -> Ordering {
+    cmp_long_run_by(&Manhattan, &[0; D], a, b).expect("Manhattan ranks never overflow")
+}
+
+/// Finds the particle that stays closest to `origin` under `metric` in the
+/// long run.
+///
+/// Fails if `metric` can't rank some particle without overflowing.
+pub fn closest_in_long_run_by<M: Metric, const D: usize>(
+    metric: &M,
+    origin: &[i64; D],
+    particles: &[Particle<D>],
+) -> Result<Closest, ParticleError> {
+    let keys = particles
+        .iter()
+        .enumerate()
+        .map(|(index, particle)| {
+            metric
+                .long_run(&particle.offset_polynomials(origin))
+                .ok_or(ParticleError::LongRunOverflow { index })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut closest: Vec<usize> = Vec::new();
+
+    for (i, key) in keys.iter().enumerate() {
+        match closest.first().map(|&j| key.cmp(&keys[j])) {
+            Some(Ordering::Greater) => {}
+            Some(Ordering::Equal) => closest.push(i),
+            Some(Ordering::Less) | None => {
+                closest.clear();
+                closest.push(i);
+            }
+        }
+    }
+
+    Ok(if closest.len() == 1 {
+        Closest::Unique(closest[0])
+    } else {
+        Closest::Tied(closest)
+    })
+}
+
+/// Finds the particle that stays closest to the origin by Manhattan distance
+/// in the long run.
+pub fn closest_in_long_run<const D: usize>(particles: &[Particle<D>]) -> Closest {
+    closest_in_long_run_by(&Manhattan, &[0; D], particles).expect("Manhattan ranks never overflow")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::particle;
+    use crate::solve_part1;
+
+    #[test]
+    fn slower_acceleration_wins_despite_distance() {
+        let particles = [particle([0], [100], [2]), particle([1_000_000], [0], [1])];
+        assert_eq!(closest_in_long_run(&particles), Closest::Unique(1));
+        assert_eq!(solve_part1(&particles), 1);
+    }
+
+    #[test]
+    fn ties_are_reported() {
+        let particles = [
+            particle([5], [0], [0]),
+            particle([1], [0], [0]),
+            particle([-1], [0], [0]),
+        ];
+        assert_eq!(closest_in_long_run(&particles), Closest::Tied(vec![1, 2]));
+        assert_eq!(solve_part1(&particles), 1);
+    }
+}
@@ -1,5 +1,136 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::Path,
+    process,
+};
+
+use day20::{LoadError, Particle, Trajectory};
+
+const USAGE: &str = "usage: day20 [PATH | -]
+       day20 export (csv | svg) [--ticks FROM..TO] [--axes AXES] [--out PATH] [PATH | -]";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("day20: {}", message);
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/// Loads particles from `path`, from stdin if it's `-`, or falls back to the
+/// puzzle input baked into the crate.
+fn load(path: Option<&str>) -> Vec<Particle> {
+    let particles = match path {
+        None => day20::load_input().map_err(LoadError::from),
+        Some("-") => day20::load_input_from(io::stdin().lock()),
+        Some(path) => File::open(path)
+            .map_err(LoadError::from)
+            .and_then(day20::load_input_from),
+    };
+
+    particles.unwrap_or_else(|err| fail(err))
+}
+
+fn parse_ticks(ticks: &str) -> Option<Range<i64>> {
+    let (from, to) = ticks.split_once("..")?;
+    let (from, to) = (from.parse().ok()?, to.parse().ok()?);
+    if 0 <= from && from <= to {
+        Some(from..to)
+    } else {
+        None
+    }
+}
+
+fn parse_axes(axes: &str) -> Option<[usize; 2]> {
+    let mut axes = axes.chars().map(|axis| "xyz".find(axis));
+    match (axes.next(), axes.next(), axes.next()) {
+        (Some(Some(h)), Some(Some(v)), None) => Some([h, v]),
+        _ => None,
+    }
+}
+
+/// Writes the frames in `ticks` as CSV or as one SVG file per tick.
+fn export(args: &[&str]) {
+    let (svg, mut args) = match args.split_first() {
+        Some((&"csv", rest)) => (false, rest.iter()),
+        Some((&"svg", rest)) => (true, rest.iter()),
+        _ => usage(),
+    };
+
+    let mut ticks = 0..100;
+    let mut axes = [0, 1];
+    let mut out = None;
+    let mut input = None;
+
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--ticks" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                ticks = parse_ticks(value)
+                    .unwrap_or_else(|| fail(format!("invalid tick range `{}`", value)));
+            }
+            "--axes" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                axes =
+                    parse_axes(value).unwrap_or_else(|| fail(format!("invalid axes `{}`", value)));
+            }
+            "--out" => out = Some(*args.next().unwrap_or_else(|| usage())),
+            _ if input.is_none() && (arg == "-" || !arg.starts_with('-')) => input = Some(arg),
+            _ => usage(),
+        }
+    }
+
+    let frames = Trajectory::new(&load(input))
+        .skip(ticks.start as usize)
+        .take((ticks.end - ticks.start) as usize);
+
+    let result = if svg {
+        let dir = Path::new(out.unwrap_or("frames"));
+        let frames = frames.collect::<Vec<_>>();
+
+        fs::create_dir_all(dir).and_then(|()| {
+            for (frame, svg) in frames.iter().zip(day20::svg_frames(&frames, axes)) {
+                fs::write(dir.join(format!("tick_{:06}.svg", frame.tick)), svg)?;
+            }
+            Ok(())
+        })
+    } else {
+        match out {
+            Some(path) => {
+                File::create(path).and_then(|file| day20::write_csv(BufWriter::new(file), frames))
+            }
+            None => {
+                let stdout = io::stdout();
+                let mut stdout = BufWriter::new(stdout.lock());
+                day20::write_csv(&mut stdout, frames).and_then(|()| stdout.flush())
+            }
+        }
+    };
+
+    result.unwrap_or_else(|err| fail(err));
+}
+
 fn main() {
-    let (part1, part2) = day20::solve();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let mut particles = match args[..] {
+        ["export", ref rest @ ..] => return export(rest),
+        [] => load(None),
+        [path] => load(Some(path)),
+        _ => usage(),
+    };
+    if particles.is_empty() {
+        fail("the input has no particles");
+    }
+
+    let part1 = day20::solve_part1(&particles);
+    let part2 = day20::solve_part2(&mut particles).unwrap_or_else(|err| fail(err));
     println!("{}", part1);
     println!("{}", part2);
-}
\ No newline at end of file
+}
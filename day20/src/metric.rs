@@ -0,0 +1,184 @@
+//! Ways of measuring how far a particle is from a reference point.
+
+/// A distance function, along with enough structure to rank particles by it
+/// once they've flown far enough that the ranking never changes again.
+pub trait Metric {
+    /// The distance between two points `delta` apart, or `None` if it
+    /// overflows.
+    fn norm(&self, delta: &[i128]) -> Option<i128>;
+
+    /// The coefficients, highest power of `t` first, of a polynomial that
+    /// ranks particles the same way this metric does for every large enough
+    /// `t`.
+    ///
+    /// `axes` describes each axis of the particle's offset from the reference
+    /// point as `[α, β, γ]`, meaning `2 * offset(t) == α*t² + β*t + γ`.
+    /// Returns `None` if a coefficient overflows.
+    fn long_run(&self, axes: &[[i128; 3]]) -> Option<Vec<i128>>;
+}
+
+/// `axis` multiplied by the sign it has for every large enough `t`, which is
+/// the sign of its first nonzero coefficient.
+fn settled(axis: [i128; 3]) -> [i128; 3] {
+    let sign = axis.iter().find(|&&c| c != 0).map_or(0, |c| c.signum());
+    [sign * axis[0], sign * axis[1], sign * axis[2]]
+}
+
+/// The sum of the absolute differences on each axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    fn norm(&self, delta: &[i128]) -> Option<i128> {
+        delta
+            .iter()
+            .try_fold(0i128, |sum, x| sum.checked_add(x.checked_abs()?))
+    }
+
+    /// Each coefficient is below 2^66 in magnitude, so the sums can't
+    /// overflow for any number of axes an array can hold.
+    fn long_run(&self, axes: &[[i128; 3]]) -> Option<Vec<i128>> {
+        let mut sum = vec![0i128; 3];
+        for &axis in axes {
+            for (sum, c) in sum.iter_mut().zip(&settled(axis)) {
+                *sum = sum.checked_add(*c)?;
+            }
+        }
+        Some(sum)
+    }
+}
+
+/// The square of the straight-line distance, which keeps it an integer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EuclideanSquared;
+
+impl Metric for EuclideanSquared {
+    fn norm(&self, delta: &[i128]) -> Option<i128> {
+        delta
+            .iter()
+            .try_fold(0i128, |sum, x| sum.checked_add(x.checked_mul(*x)?))
+    }
+
+    /// The squared coefficients overflow once a coordinate, velocity or
+    /// acceleration gets past about 2^61 in magnitude.
+    fn long_run(&self, axes: &[[i128; 3]]) -> Option<Vec<i128>> {
+        let product = |x: i128, y: i128, factor: i128| x.checked_mul(y)?.checked_mul(factor);
+
+        // (α*t² + β*t + γ)² expanded, summed over every axis.
+        let mut sum = vec![0i128; 5];
+        for &[a, b, c] in axes {
+            let terms = [
+                product(a, a, 1)?,
+                product(a, b, 2)?,
+                product(b, b, 1)?.checked_add(product(a, c, 2)?)?,
+                product(b, c, 2)?,
+                product(c, c, 1)?,
+            ];
+            for (sum, term) in sum.iter_mut().zip(terms) {
+                *sum = sum.checked_add(term)?;
+            }
+        }
+        Some(sum)
+    }
+}
+
+/// The largest absolute difference on any one axis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Chebyshev;
+
+impl Metric for Chebyshev {
+    fn norm(&self, delta: &[i128]) -> Option<i128> {
+        delta
+            .iter()
+            .try_fold(0i128, |max, x| Some(max.max(x.checked_abs()?)))
+    }
+
+    fn long_run(&self, axes: &[[i128; 3]]) -> Option<Vec<i128>> {
+        // Past every crossing point, the axis that ends up farthest is the one
+        // whose settled polynomial is lexicographically largest.
+        let farthest = axes.iter().map(|&axis| settled(axis)).max();
+        Some(farthest.unwrap_or([0; 3]).to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        closest_in_long_run, closest_in_long_run_by, closest_particle, particle, Closest,
+        ParticleError,
+    };
+
+    #[test]
+    fn metrics_disagree_on_who_is_closest() {
+        // Diagonal, but no coordinate as large as the other's.
+        let particles = [
+            particle([3, 3], [0, 0], [0, 0]),
+            particle([5, 0], [0, 0], [0, 0]),
+        ];
+
+        assert_eq!(
+            closest_particle(&particles, &Manhattan, &[0; 2], 0),
+            Ok(Some(1))
+        );
+        assert_eq!(
+            closest_particle(&particles, &Chebyshev, &[0; 2], 0),
+            Ok(Some(0))
+        );
+        assert_eq!(
+            closest_particle(&particles, &EuclideanSquared, &[0; 2], 0),
+            Ok(Some(0))
+        );
+
+        // Measured from elsewhere, the ranking flips.
+        assert_eq!(
+            closest_particle(&particles, &Chebyshev, &[6, 0], 0),
+            Ok(Some(1))
+        );
+    }
+
+    #[test]
+    fn long_run_ranks_follow_each_metric() {
+        let particles = [
+            particle([0, 0], [0, 0], [1, 1]),
+            particle([0, 0], [0, 0], [2, 0]),
+            particle([0, 0], [0, 0], [0, -2]),
+        ];
+
+        assert_eq!(
+            closest_in_long_run(&particles),
+            Closest::Tied(vec![0, 1, 2])
+        );
+        assert_eq!(
+            closest_in_long_run_by(&Chebyshev, &[0; 2], &particles),
+            Ok(Closest::Unique(0))
+        );
+        assert_eq!(
+            closest_in_long_run_by(&EuclideanSquared, &[0; 2], &particles),
+            Ok(Closest::Unique(0))
+        );
+
+        // Far enough out, the closest on every tick is the long-run one.
+        assert_eq!(
+            closest_particle(&particles, &Chebyshev, &[0; 2], 1000),
+            Ok(Some(0))
+        );
+    }
+
+    #[test]
+    fn huge_euclidean_ranks_report_overflow() {
+        let particles = [
+            particle([0; 3], [0; 3], [1; 3]),
+            particle([i64::MAX; 3], [i64::MAX; 3], [i64::MAX; 3]),
+        ];
+
+        assert_eq!(
+            closest_in_long_run_by(&EuclideanSquared, &[0; 3], &particles),
+            Err(ParticleError::LongRunOverflow { index: 1 })
+        );
+        assert_eq!(
+            closest_in_long_run_by(&Chebyshev, &[0; 3], &particles),
+            Ok(Closest::Unique(0))
+        );
+    }
+}
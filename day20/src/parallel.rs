@@ -0,0 +1,72 @@
+//! The sort-and-sweep simulation spread across threads. Particles keep their
+//! relative order, so survivors come out exactly as in the sequential engines.
+
+use rayon::prelude::*;
+
+use crate::{proof::Prover, retain_alive, until_settled, Particle, ParticleError};
+
+/// Ticks every particle and sets `alive[i]` to whether no other particle
+/// landed on the same position as particle `i`.
+///
+/// Fails with the first `i` whose particle overflows, as `tick_all` does.
+fn tick_all<const D: usize>(
+    particles: &mut [Particle<D>],
+    keys: &mut Vec<([i64; D], usize)>,
+    dead: &mut Vec<usize>,
+    alive: &mut Vec<bool>,
+) -> Result<(), usize> {
+    let overflowed = particles
+        .par_iter_mut()
+        .enumerate()
+        .filter_map(|(i, particle)| particle.tick().is_none().then_some(i))
+        .min();
+    if let Some(i) = overflowed {
+        return Err(i);
+    }
+
+    particles
+        .par_iter()
+        .enumerate()
+        .map(|(i, particle)| (particle.pos, i))
+        .collect_into_vec(keys);
+    keys.par_sort_unstable();
+
+    let keys = &keys[..];
+    dead.clear();
+    dead.par_extend(
+        (0..keys.len())
+            .into_par_iter()
+            .filter(|&k| {
+                (k > 0 && keys[k - 1].0 == keys[k].0)
+                    || (k + 1 < keys.len() && keys[k + 1].0 == keys[k].0)
+            })
+            .map(|k| keys[k].1),
+    );
+
+    alive.clear();
+    alive.resize(particles.len(), true);
+    dead.iter().for_each(|&i| alive[i] = false);
+    Ok(())
+}
+
+pub(crate) fn simulate<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+) -> Result<i64, ParticleError> {
+    let mut ids = (0..particles.len()).collect::<Vec<_>>();
+    let mut keys = Vec::with_capacity(particles.len());
+    let mut dead = Vec::new();
+    let mut alive = Vec::with_capacity(particles.len());
+    let mut prover = Prover::new();
+
+    until_settled(
+        particles,
+        |particles| prover.particles(particles),
+        |particles| {
+            tick_all(particles, &mut keys, &mut dead, &mut alive).map_err(|i| ids[i])?;
+
+            retain_alive(&mut ids, &alive);
+            retain_alive(particles, &alive);
+            Ok(!dead.is_empty())
+        },
+    )
+}
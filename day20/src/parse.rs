@@ -0,0 +1,413 @@
+//! A forgiving parser for `p=<x,y,z>, v=<x,y,z>, a=<x,y,z>` lines that reports
+//! exactly where and why a line was rejected.
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read},
+    num::ParseIntError,
+    str::FromStr,
+};
+
+use crate::Particle;
+
+/// One of the three vectors that make up a particle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Pos,
+    Vel,
+    Accel,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Pos => write!(f, "p"),
+            Field::Vel => write!(f, "v"),
+            Field::Accel => write!(f, "a"),
+        }
+    }
+}
+
+/// The conventional names of the first four axes.
+pub(crate) const AXES: [&str; 4] = ["x", "y", "z", "w"];
+
+/// The part of a particle a parse error refers to, such as `v` or `v.y`. Axes
+/// past the fourth are shown by index, as in `v[4]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Component {
+    pub field: Field,
+    pub axis: Option<usize>,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.field.fmt(f)?;
+        match self.axis {
+            Some(axis) if axis < AXES.len() => write!(f, ".{}", AXES[axis]),
+            Some(axis) => write!(f, "[{}]", axis),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// Something other than what the grammar allows was found.
+    Expected(&'static str),
+    /// A number was found but doesn't fit in an `i64`.
+    InvalidNumber(ParseIntError),
+    /// The line ended without this field.
+    MissingField(Field),
+    /// This field appeared more than once.
+    DuplicateField(Field),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::Expected(what) => write!(f, "expected {}", what),
+            ParseErrorKind::InvalidNumber(err) => write!(f, "invalid number: {}", err),
+            ParseErrorKind::MissingField(field) => write!(f, "missing field `{}`", field),
+            ParseErrorKind::DuplicateField(field) => write!(f, "duplicate field `{}`", field),
+        }
+    }
+}
+
+/// Why and where a particle failed to parse. Lines and columns start at 1, and
+/// columns count characters rather than bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseParticleError {
+    pub line: usize,
+    pub column: usize,
+    pub component: Option<Component>,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseParticleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if let Some(component) = self.component {
+            write!(f, " ({})", component)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl Error for ParseParticleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ParseErrorKind::InvalidNumber(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+struct Cursor<'a> {
+    line: &'a str,
+    pos: usize,
+    component: Option<Component>,
+}
+
+impl<'a> Cursor<'a> {
+    fn error_at(&self, pos: usize, kind: ParseErrorKind) -> ParseParticleError {
+        ParseParticleError {
+            line: 1,
+            column: self.line[..pos].chars().count() + 1,
+            component: self.component,
+            kind,
+        }
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseParticleError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.line[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, ch: char, what: &'static str) -> Result<(), ParseParticleError> {
+        if self.eat(ch) {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorKind::Expected(what)))
+        }
+    }
+
+    fn field(&mut self) -> Result<Field, ParseParticleError> {
+        self.skip_whitespace();
+        let field = match self.rest().chars().next() {
+            Some('p') => Field::Pos,
+            Some('v') => Field::Vel,
+            Some('a') => Field::Accel,
+            _ => return Err(self.error(ParseErrorKind::Expected("`p`, `v` or `a`"))),
+        };
+        self.pos += 1;
+        Ok(field)
+    }
+
+    fn number(&mut self) -> Result<i64, ParseParticleError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let rest = self.rest();
+
+        let sign = rest.starts_with(['-', '+']) as usize;
+        let digits = rest[sign..].bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return Err(self.error(ParseErrorKind::Expected("a number")));
+        }
+
+        self.pos += sign + digits;
+        self.line[start..self.pos]
+            .parse()
+            .map_err(|err| self.error_at(start, ParseErrorKind::InvalidNumber(err)))
+    }
+
+    fn vector<const D: usize>(&mut self, field: Field) -> Result<[i64; D], ParseParticleError> {
+        self.component = Some(Component { field, axis: None });
+        self.expect('=', "`=`")?;
+        self.expect('<', "`<`")?;
+
+        let mut vector = [0; D];
+        for (axis, slot) in vector.iter_mut().enumerate() {
+            self.component = Some(Component {
+                field,
+                axis: Some(axis),
+            });
+            if axis != 0 {
+                self.expect(',', "`,`")?;
+            }
+            *slot = self.number()?;
+        }
+
+        self.component = Some(Component { field, axis: None });
+        self.expect('>', "`>`")?;
+        self.component = None;
+
+        Ok(vector)
+    }
+}
+
+impl<const D: usize> FromStr for Particle<D> {
+    type Err = ParseParticleError;
+
+    /// Parses a single line, allowing any whitespace between tokens and the
+    /// three fields in any order.
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut cursor = Cursor {
+            line,
+            pos: 0,
+            component: None,
+        };
+        let mut fields = [None; 3];
+
+        for i in 0..3 {
+            cursor.skip_whitespace();
+            if cursor.rest().is_empty() {
+                let missing = [Field::Pos, Field::Vel, Field::Accel]
+                    .iter()
+                    .find(|&&field| fields[field as usize].is_none())
+                    .unwrap();
+                return Err(cursor.error(ParseErrorKind::MissingField(*missing)));
+            }
+
+            if i != 0 {
+                cursor.expect(',', "`,`")?;
+                cursor.skip_whitespace();
+            }
+
+            let start = cursor.pos;
+            let field = cursor.field()?;
+            let slot = &mut fields[field as usize];
+            if slot.is_some() {
+                return Err(cursor.error_at(start, ParseErrorKind::DuplicateField(field)));
+            }
+            *slot = Some(cursor.vector(field)?);
+        }
+
+        cursor.skip_whitespace();
+        if !cursor.rest().is_empty() {
+            return Err(cursor.error(ParseErrorKind::Expected("end of line")));
+        }
+
+        match fields {
+            [Some(pos), Some(vel), Some(accel)] => Ok(Self { pos, vel, accel }),
+            _ => unreachable!("three distinct fields were parsed"),
+        }
+    }
+}
+
+/// Formats the particle the way the puzzle input does, so that its output
+/// parses back into the same particle.
+impl<const D: usize> fmt::Display for Particle<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (field, vector)) in [("p", &self.pos), ("v", &self.vel), ("a", &self.accel)]
+            .iter()
+            .enumerate()
+        {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}=<", field)?;
+            for (axis, x) in vector.iter().enumerate() {
+                if axis != 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{}", x)?;
+            }
+            write!(f, ">")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses one particle per line, skipping blank lines.
+pub fn parse_input<const D: usize>(input: &str) -> Result<Vec<Particle<D>>, ParseParticleError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            line.parse()
+                .map_err(|err| ParseParticleError { line: i + 1, ..err })
+        })
+        .collect()
+}
+
+/// Why particles couldn't be loaded from a reader.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(ParseParticleError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "couldn't read input: {}", err),
+            LoadError::Parse(err) => write!(f, "couldn't parse input: {}", err),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            LoadError::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<ParseParticleError> for LoadError {
+    fn from(err: ParseParticleError) -> Self {
+        LoadError::Parse(err)
+    }
+}
+
+/// Reads all of `reader` and parses it like `parse_input`.
+pub fn load_input_from<const D: usize>(
+    mut reader: impl Read,
+) -> Result<Vec<Particle<D>>, LoadError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(parse_input(&input)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::particle;
+
+    fn error(line: &str) -> ParseParticleError {
+        line.parse::<Particle>().unwrap_err()
+    }
+
+    fn component(field: Field, axis: Option<usize>) -> Option<Component> {
+        Some(Component { field, axis })
+    }
+
+    #[test]
+    fn whitespace_and_field_order_are_free() {
+        let expected = particle([1, 2, 3], [4, 5, 6], [7, 8, 9]);
+        assert_eq!("p=< 1,2,3>, v=<4,5,6>, a=<7,8,9>".parse(), Ok(expected));
+        assert_eq!(
+            "  a = < 7 , 8 , 9 > ,v=<4,5,6>,p=<1, 2, 3>  ".parse(),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn trailing_junk_is_rejected() {
+        let err = error("p=<1,2,3>, v=<4,5,6>, a=<7,8,9> x");
+        assert_eq!((err.line, err.column), (1, 33));
+        assert_eq!(err.component, None);
+        assert_eq!(err.kind, ParseErrorKind::Expected("end of line"));
+    }
+
+    #[test]
+    fn bad_numbers_point_at_their_component() {
+        let err = error("p=<1,2,3>, v=<4,x,6>, a=<7,8,9>");
+        assert_eq!(err.column, 17);
+        assert_eq!(err.component, component(Field::Vel, Some(1)));
+        assert_eq!(err.kind, ParseErrorKind::Expected("a number"));
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 17 (v.y): expected a number"
+        );
+
+        let err = error("p=<1,2,3>, v=<4,99999999999999999999,6>, a=<7,8,9>");
+        assert_eq!(err.column, 17);
+        assert_eq!(err.component, component(Field::Vel, Some(1)));
+        assert!(matches!(err.kind, ParseErrorKind::InvalidNumber(_)));
+    }
+
+    #[test]
+    fn fields_must_appear_exactly_once() {
+        let err = error("p=<1,2,3>, p=<4,5,6>, a=<7,8,9>");
+        assert_eq!(err.column, 12);
+        assert_eq!(err.kind, ParseErrorKind::DuplicateField(Field::Pos));
+
+        let err = error("p=<1,2,3>, a=<7,8,9>");
+        assert_eq!(err.column, 21);
+        assert_eq!(err.kind, ParseErrorKind::MissingField(Field::Vel));
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let err = error("\u{3000}p=<1,2,x>, v=<4,5,6>, a=<7,8,9>");
+        assert_eq!(err.column, 9);
+        assert_eq!(err.component, component(Field::Pos, Some(2)));
+    }
+
+    #[test]
+    fn errors_carry_their_line_number() {
+        let input = "p=<1,2,3>, v=<4,5,6>, a=<7,8,9>\n\n  \np=<1,2,3>, v=<4,5,6>, a=<7,8>\n";
+        let err = parse_input::<3>(input).unwrap_err();
+        assert_eq!((err.line, err.column), (4, 29));
+        assert_eq!(err.component, component(Field::Accel, Some(2)));
+        assert_eq!(err.kind, ParseErrorKind::Expected("`,`"));
+    }
+}
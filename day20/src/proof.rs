@@ -0,0 +1,199 @@
+//! A proof that the swarm is done colliding: if, along every axis, particles
+//! further out also move and accelerate at least as fast outwards, nobody can
+//! ever catch up with anybody else. Failing that, it's enough for each pair to
+//! have some axis of its own along which that holds.
+
+use std::array;
+
+use crate::Particle;
+
+/// Whether particles on one axis, given as `(pos, vel, accel)`, keep their
+/// order forever: going from lower to higher positions, neither velocity nor
+/// acceleration ever decreases.
+fn axis_settled(axis: &mut [(i64, i64, i64)]) -> bool {
+    axis.sort_unstable();
+
+    let (mut max_vel, mut max_accel) = (i64::MIN, i64::MIN);
+    for same_pos in axis.chunk_by(|a, b| a.0 == b.0) {
+        let min_vel = same_pos[0].1;
+        let min_accel = same_pos.iter().map(|&(_, _, a)| a).min().unwrap();
+        if min_vel < max_vel || min_accel < max_accel {
+            return false;
+        }
+
+        max_vel = max_vel.max(same_pos[same_pos.len() - 1].1);
+        max_accel = max_accel.max(same_pos.iter().map(|&(_, _, a)| a).max().unwrap());
+    }
+
+    true
+}
+
+/// Whether two particles, given as `(pos, vel, accel)` along one axis, stay
+/// apart along it forever: the one ahead is at least as fast and accelerating
+/// at least as hard.
+fn apart(a: (i64, i64, i64), b: (i64, i64, i64)) -> bool {
+    let (behind, ahead) = if a.0 < b.0 { (a, b) } else { (b, a) };
+    behind.0 != ahead.0 && behind.1 <= ahead.1 && behind.2 <= ahead.2
+}
+
+/// The buffers for proving that a swarm is settled, kept around so that
+/// repeated attempts don't allocate.
+#[derive(Debug)]
+pub(crate) struct Prover<const D: usize> {
+    axis: Vec<(i64, i64, i64)>,
+    positions: Vec<[i64; D]>,
+    states: Vec<[(i64, i64, i64); D]>,
+}
+
+impl<const D: usize> Prover<D> {
+    pub(crate) fn new() -> Self {
+        Self {
+            axis: Vec::new(),
+            positions: Vec::new(),
+            states: Vec::new(),
+        }
+    }
+
+    /// `no_future_collisions` for `len` particles whose state along each axis
+    /// is given by `state(particle, axis)` as `(pos, vel, accel)`.
+    pub(crate) fn settled(
+        &mut self,
+        len: usize,
+        state: impl Fn(usize, usize) -> (i64, i64, i64),
+    ) -> bool {
+        let axis = &mut self.axis;
+        let ordered = (0..D).all(|a| {
+            axis.clear();
+            axis.extend((0..len).map(|i| state(i, a)));
+            axis_settled(axis)
+        });
+
+        // Each pair must also differ along some axis for that axis to keep them
+        // apart.
+        if ordered {
+            self.positions.clear();
+            self.positions
+                .extend((0..len).map(|i| array::from_fn(|axis| state(i, axis).0)));
+            self.positions.sort_unstable();
+            if self.positions.windows(2).all(|pair| pair[0] != pair[1]) {
+                return true;
+            }
+        }
+
+        // Otherwise look at every pair on its own. That's quadratic, but gives
+        // up at the first pair no axis keeps apart, which comes early unless
+        // the swarm is close to settled anyway.
+        self.states.clear();
+        self.states
+            .extend((0..len).map(|i| array::from_fn(|axis| state(i, axis))));
+        let states = &self.states[..];
+        states.iter().enumerate().all(|(i, a)| {
+            states[i + 1..]
+                .iter()
+                .all(|b| (0..D).any(|axis| apart(a[axis], b[axis])))
+        })
+    }
+
+    pub(crate) fn particles(&mut self, particles: &[Particle<D>]) -> bool {
+        self.settled(particles.len(), |i, axis| {
+            let particle = &particles[i];
+            (particle.pos[axis], particle.vel[axis], particle.accel[axis])
+        })
+    }
+}
+
+/// When to try proving a swarm settled next. Each attempt sorts the whole
+/// swarm several times over, so it's made right away and then after stretches
+/// of ticks that grow by half each time, starting over whenever particles
+/// collide, as the swarm can't be settled while they still do.
+///
+/// The proof may thus come about half as many ticks again after the last
+/// collision as it could have.
+#[derive(Debug)]
+pub(crate) struct Schedule {
+    next: i64,
+    gap: i64,
+}
+
+impl Schedule {
+    pub(crate) fn new() -> Self {
+        Self { next: 0, gap: 1 }
+    }
+
+    /// Whether to attempt the proof after `tick` ticks.
+    pub(crate) fn due(&mut self, tick: i64) -> bool {
+        if tick < self.next {
+            return false;
+        }
+
+        self.next = tick + self.gap;
+        self.gap += self.gap / 2 + 1;
+        true
+    }
+
+    /// Notes that particles collided on `tick`, so that the next attempt comes
+    /// after the first tick without a collision.
+    pub(crate) fn collided(&mut self, tick: i64) {
+        self.next = tick + 1;
+        self.gap = 1;
+    }
+}
+
+/// Whether no two of `particles` can ever collide again, no matter how many
+/// ticks pass.
+///
+/// This holds once every pair of particles has an axis along which they're at
+/// different positions and the one ahead is at least as fast and accelerating
+/// at least as hard: the gap between them can then only grow. It's quickest to
+/// check when one such axis works for all of them.
+pub fn no_future_collisions<const D: usize>(particles: &[Particle<D>]) -> bool {
+    Prover::new().particles(particles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::particle;
+
+    #[test]
+    fn one_axis_can_keep_everyone_apart() {
+        let particles = [
+            particle([0, 5], [0, -1], [0, 0]),
+            particle([1, 0], [1, 0], [0, 0]),
+            particle([2, -5], [1, 1], [1, 0]),
+        ];
+        assert!(no_future_collisions(&particles));
+    }
+
+    #[test]
+    fn each_pair_can_use_its_own_axis() {
+        // Neither axis alone keeps all three apart, but each pair has one.
+        let particles = [
+            particle([0, 0], [0, 0], [0, 0]),
+            particle([5, 0], [1, 1], [0, 0]),
+            particle([10, 5], [-1, 1], [0, 0]),
+        ];
+        assert!(no_future_collisions(&particles));
+    }
+
+    #[test]
+    fn approaching_particles_can_collide() {
+        let particles = [
+            particle([0, 0], [1, 0], [0, 0]),
+            particle([10, 0], [-1, 0], [0, 0]),
+        ];
+        assert!(!no_future_collisions(&particles));
+    }
+
+    #[test]
+    fn schedule_backs_off_and_restarts_after_collisions() {
+        let mut schedule = Schedule::new();
+        let due = (0..20).filter(|&t| schedule.due(t)).collect::<Vec<_>>();
+        assert_eq!(due, vec![0, 1, 3, 7, 14]);
+
+        schedule.collided(20);
+        assert!(!schedule.due(20));
+        assert!(schedule.due(21));
+        assert!(schedule.due(22));
+    }
+}
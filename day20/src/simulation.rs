@@ -0,0 +1,315 @@
+//! A simulation that can be paused, saved and resumed elsewhere.
+
+use std::collections::{BTreeSet, HashMap};
+
+#[cfg(feature = "serde")]
+use bincode::Options;
+
+use crate::{
+    proof::{Prover, Schedule},
+    tick_all, Particle, ParticleError,
+};
+
+/// A swarm being ticked forward under the puzzle's collision rule.
+///
+/// Destroyed particles stay in place, frozen as they were when they collided,
+/// so indices keep referring to the same particle throughout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Simulation<const D: usize = 3> {
+    particles: Vec<Particle<D>>,
+    tick: i64,
+    destroyed: BTreeSet<usize>,
+}
+
+impl<const D: usize> Simulation<D> {
+    pub fn new(particles: Vec<Particle<D>>) -> Self {
+        Self {
+            particles,
+            tick: 0,
+            destroyed: BTreeSet::new(),
+        }
+    }
+
+    /// How many ticks have been simulated so far.
+    pub fn tick(&self) -> i64 {
+        self.tick
+    }
+
+    pub fn particles(&self) -> &[Particle<D>] {
+        &self.particles
+    }
+
+    /// The indices of every particle destroyed so far.
+    pub fn destroyed(&self) -> &BTreeSet<usize> {
+        &self.destroyed
+    }
+
+    pub fn survivors(&self) -> usize {
+        self.particles.len() - self.destroyed.len()
+    }
+
+    fn alive(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.particles.len()).filter(move |i| !self.destroyed.contains(i))
+    }
+
+    /// Advances every surviving particle by one tick and destroys the ones
+    /// that end up sharing a position.
+    ///
+    /// Fails if a particle gets too far away for an `i64`, leaving the
+    /// simulation as it was before the tick.
+    pub fn step(&mut self) -> Result<(), ParticleError> {
+        let alive = self.alive().collect::<Vec<_>>();
+        let mut moved = alive.iter().map(|&i| self.particles[i]).collect::<Vec<_>>();
+        let mut positions = HashMap::new();
+
+        tick_all(&mut moved, &mut positions).map_err(|i| ParticleError::Overflow {
+            index: alive[i],
+            t: self.tick + 1,
+        })?;
+
+        for (i, particle) in alive.into_iter().zip(moved) {
+            if positions[&particle.pos] > 1 {
+                self.destroyed.insert(i);
+            }
+            self.particles[i] = particle;
+        }
+
+        self.tick += 1;
+        Ok(())
+    }
+
+    /// Whether `no_future_collisions` holds for the surviving particles.
+    pub fn is_settled(&self) -> bool {
+        self.settled(&mut Prover::new())
+    }
+
+    fn settled(&self, prover: &mut Prover<D>) -> bool {
+        let alive = self.alive().collect::<Vec<_>>();
+        prover.settled(alive.len(), |i, axis| {
+            let particle = &self.particles[alive[i]];
+            (particle.pos[axis], particle.vel[axis], particle.accel[axis])
+        })
+    }
+
+    /// Steps until no collision can happen anymore and returns how many
+    /// particles survived, which is what `solve_part2` would return for the
+    /// initial swarm.
+    pub fn run(&mut self) -> Result<usize, ParticleError> {
+        let mut prover = Prover::new();
+        let mut schedule = Schedule::new();
+
+        while !(schedule.due(self.tick) && self.settled(&mut prover)) {
+            let destroyed = self.destroyed.len();
+            self.step()?;
+            if self.destroyed.len() > destroyed {
+                schedule.collided(self.tick);
+            }
+        }
+
+        Ok(self.survivors())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const D: usize> Simulation<D> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// A compact binary snapshot: bincode with variable-length integers, so
+    /// small coordinates take a byte or two each.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::DefaultOptions::new().serialize(self).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::DefaultOptions::new().deserialize(bytes)
+    }
+}
+
+/// `Simulation` is (de)serialized as `{ particles, tick, destroyed }` by hand,
+/// as serde's derive can't handle the default on `D`, and so that snapshots
+/// destroying particles they don't have are rejected.
+#[cfg(feature = "serde")]
+mod simulation_serde {
+    use std::collections::BTreeSet;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::Simulation;
+    use crate::Particle;
+
+    #[derive(Serialize)]
+    #[serde(rename = "Simulation")]
+    struct Borrowed<'a, const D: usize> {
+        particles: &'a [Particle<D>],
+        tick: i64,
+        destroyed: &'a BTreeSet<usize>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Simulation")]
+    struct Owned<const D: usize> {
+        particles: Vec<Particle<D>>,
+        tick: i64,
+        destroyed: BTreeSet<usize>,
+    }
+
+    impl<const D: usize> Serialize for Simulation<D> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Borrowed {
+                particles: &self.particles,
+                tick: self.tick,
+                destroyed: &self.destroyed,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, const D: usize> Deserialize<'de> for Simulation<D> {
+        fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+            let owned = Owned::deserialize(deserializer)?;
+            let len = owned.particles.len();
+            if let Some(&index) = owned.destroyed.range(len..).next() {
+                return Err(De::Error::custom(format!(
+                    "destroyed particle {} is out of range for {} particles",
+                    index, len
+                )));
+            }
+
+            Ok(Simulation {
+                particles: owned.particles,
+                tick: owned.tick,
+                destroyed: owned.destroyed,
+            })
+        }
+    }
+}
+
+/// `Particle` is (de)serialized as `{ pos, vel, accel }` with each vector as a
+/// sequence, since serde only implements its traits for arrays of up to 32
+/// elements rather than for every `D`.
+#[cfg(feature = "serde")]
+mod particle_serde {
+    use std::convert::TryInto;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::Particle;
+
+    #[derive(Serialize)]
+    #[serde(rename = "Particle")]
+    struct Borrowed<'a> {
+        pos: &'a [i64],
+        vel: &'a [i64],
+        accel: &'a [i64],
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename = "Particle")]
+    struct Owned {
+        pos: Vec<i64>,
+        vel: Vec<i64>,
+        accel: Vec<i64>,
+    }
+
+    impl<const D: usize> Serialize for Particle<D> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            Borrowed {
+                pos: &self.pos,
+                vel: &self.vel,
+                accel: &self.accel,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, const D: usize> Deserialize<'de> for Particle<D> {
+        fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+            let owned = Owned::deserialize(deserializer)?;
+            let vector = |v: Vec<i64>| {
+                let len = v.len();
+                v.try_into().map_err(|_| {
+                    De::Error::invalid_length(len, &format!("{} coordinates", D).as_str())
+                })
+            };
+
+            Ok(Particle {
+                pos: vector(owned.pos)?,
+                vel: vector(owned.vel)?,
+                accel: vector(owned.accel)?,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_input, particle, solve_part2};
+
+    #[test]
+    fn run_matches_solve_part2() {
+        let mut input = load_input().unwrap();
+        let mut simulation = Simulation::new(input.clone());
+        assert_eq!(simulation.run(), solve_part2(&mut input));
+    }
+
+    #[test]
+    fn failed_step_leaves_simulation_untouched() {
+        let mut simulation = Simulation::new(vec![
+            particle([0], [0], [0]),
+            particle([i64::MAX], [1], [0]),
+        ]);
+        let before = simulation.clone();
+
+        assert_eq!(
+            simulation.step(),
+            Err(ParticleError::Overflow { index: 1, t: 1 })
+        );
+        assert_eq!(simulation, before);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn resumed_snapshots_match_uninterrupted_run() {
+        let mut uninterrupted = Simulation::new(load_input().unwrap());
+        let mut paused = uninterrupted.clone();
+        for _ in 0..20 {
+            uninterrupted.step().unwrap();
+            paused.step().unwrap();
+        }
+
+        let mut from_json = Simulation::from_json(&paused.to_json()).unwrap();
+        let mut from_bytes = Simulation::from_bytes(&paused.to_bytes()).unwrap();
+        assert_eq!(from_json, paused);
+        assert_eq!(from_bytes, paused);
+
+        for _ in 0..30 {
+            uninterrupted.step().unwrap();
+            from_json.step().unwrap();
+            from_bytes.step().unwrap();
+        }
+        assert_eq!(from_json, uninterrupted);
+        assert_eq!(from_bytes, uninterrupted);
+
+        let survivors = uninterrupted.run();
+        assert_eq!(from_json.run(), survivors);
+        assert_eq!(from_bytes.run(), survivors);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshots_with_unknown_destroyed_particles_are_rejected() {
+        let json = r#"{"particles":[{"pos":[0,0,0],"vel":[0,0,0],"accel":[0,0,0]}],"tick":0,"destroyed":[5,7]}"#;
+        assert!(Simulation::<3>::from_json(json).is_err());
+
+        let mut simulation = Simulation::new(vec![particle([0; 3], [0; 3], [0; 3])]);
+        simulation.destroyed.insert(5);
+        assert!(Simulation::<3>::from_bytes(&simulation.to_bytes()).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! A cache-friendly simulation: the swarm is kept as one column per coordinate
+//! and collisions are found by sorting positions instead of hashing them.
+
+use std::array;
+
+use crate::{proof::Prover, retain_alive, until_settled, Particle, ParticleError};
+
+/// The swarm as a struct of arrays. `ids` maps each row back to the index of
+/// the particle it came from.
+#[derive(Debug)]
+struct Swarm<const D: usize> {
+    ids: Vec<usize>,
+    pos: [Vec<i64>; D],
+    vel: [Vec<i64>; D],
+    accel: [Vec<i64>; D],
+}
+
+impl<const D: usize> Swarm<D> {
+    fn new(particles: &[Particle<D>]) -> Self {
+        let mut swarm = Self {
+            ids: Vec::new(),
+            pos: array::from_fn(|_| Vec::new()),
+            vel: array::from_fn(|_| Vec::new()),
+            accel: array::from_fn(|_| Vec::new()),
+        };
+
+        for (id, particle) in particles.iter().enumerate() {
+            swarm.ids.push(id);
+
+            for axis in 0..D {
+                swarm.pos[axis].push(particle.pos[axis]);
+                swarm.vel[axis].push(particle.vel[axis]);
+                swarm.accel[axis].push(particle.accel[axis]);
+            }
+        }
+
+        swarm
+    }
+
+    fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Like `Particle::tick` for every row, failing with the first row that
+    /// overflows. On failure, every row has still moved, wrapping around along
+    /// the axes where it overflowed.
+    fn tick(&mut self) -> Result<(), usize> {
+        // Checking each row separately would stop the loop from vectorizing,
+        // so only look for the culprit once something has overflowed.
+        let mut overflowed = false;
+
+        for axis in 0..D {
+            let pos = self.pos[axis].iter_mut();
+            let vel = self.vel[axis].iter_mut();
+
+            for ((p, v), a) in pos.zip(vel).zip(&self.accel[axis]) {
+                let (next_v, o1) = v.overflowing_add(*a);
+                let (next_p, o2) = p.overflowing_add(next_v);
+                *v = next_v;
+                *p = next_p;
+                overflowed |= o1 | o2;
+            }
+        }
+
+        if !overflowed {
+            return Ok(());
+        }
+
+        // Wrapping arithmetic can be undone exactly, so redo each row's tick
+        // with checks from where it started.
+        let overflows = |row: usize| {
+            (0..D).any(|axis| {
+                let (p, v, a) = (
+                    self.pos[axis][row],
+                    self.vel[axis][row],
+                    self.accel[axis][row],
+                );
+                let (prev_p, prev_v) = (p.wrapping_sub(v), v.wrapping_sub(a));
+                prev_v
+                    .checked_add(a)
+                    .and_then(|v| prev_p.checked_add(v))
+                    .is_none()
+            })
+        };
+        Err((0..self.len()).find(|&row| overflows(row)).unwrap())
+    }
+
+    /// Sets `alive[row]` to whether no other row shares its position.
+    fn find_survivors(&self, keys: &mut Vec<([i64; D], usize)>, alive: &mut Vec<bool>) {
+        keys.clear();
+        keys.extend((0..self.len()).map(|row| (array::from_fn(|axis| self.pos[axis][row]), row)));
+        keys.sort_unstable();
+
+        alive.clear();
+        alive.resize(self.len(), true);
+
+        for run in keys.chunk_by(|a, b| a.0 == b.0) {
+            if run.len() > 1 {
+                run.iter().for_each(|&(_, row)| alive[row] = false);
+            }
+        }
+    }
+
+    fn settled(&self, prover: &mut Prover<D>) -> bool {
+        prover.settled(self.len(), |row, axis| {
+            (
+                self.pos[axis][row],
+                self.vel[axis][row],
+                self.accel[axis][row],
+            )
+        })
+    }
+
+    fn retain(&mut self, alive: &[bool]) {
+        retain_alive(&mut self.ids, alive);
+        for axis in 0..D {
+            retain_alive(&mut self.pos[axis], alive);
+            retain_alive(&mut self.vel[axis], alive);
+            retain_alive(&mut self.accel[axis], alive);
+        }
+    }
+}
+
+pub(crate) fn simulate<const D: usize>(
+    particles: &mut Vec<Particle<D>>,
+) -> Result<i64, ParticleError> {
+    let mut swarm = Swarm::new(particles);
+    let mut keys = Vec::with_capacity(swarm.len());
+    let mut alive = Vec::with_capacity(swarm.len());
+    let mut prover = Prover::new();
+
+    let tick = until_settled(
+        &mut swarm,
+        |swarm| swarm.settled(&mut prover),
+        |swarm| {
+            let len = swarm.len();
+            swarm.tick().map_err(|row| swarm.ids[row])?;
+            swarm.find_survivors(&mut keys, &mut alive);
+            swarm.retain(&alive);
+            Ok(swarm.len() < len)
+        },
+    )?;
+
+    let mut survivors = vec![false; particles.len()];
+    swarm.ids.iter().for_each(|&id| survivors[id] = true);
+
+    let mut survivors = survivors.into_iter();
+    particles.retain(|_| survivors.next().unwrap());
+    Ok(tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{load_input, solve_part2_report, Engine, Generator, Particle};
+
+    /// Runs every engine on `swarm` and checks that they leave the very same
+    /// survivors.
+    fn assert_engines_agree(swarm: &[Particle]) {
+        let mut expected = swarm.to_vec();
+        let report = solve_part2_report(&mut expected, Engine::HashMap).unwrap();
+        let settled_at = report.settled_at.unwrap();
+
+        let mut sweep = swarm.to_vec();
+        assert_eq!(
+            solve_part2_report(&mut sweep, Engine::SortSweep),
+            Ok(report)
+        );
+
+        let mut analytic = swarm.to_vec();
+        solve_part2_report(&mut analytic, Engine::Analytic).unwrap();
+        assert_eq!(analytic, sweep);
+
+        #[cfg(feature = "parallel")]
+        {
+            let mut parallel = swarm.to_vec();
+            assert_eq!(
+                solve_part2_report(&mut parallel, Engine::Parallel),
+                Ok(report)
+            );
+            assert_eq!(parallel, expected);
+        }
+
+        // Unlike the others, these hand back the survivors as they started.
+        for particle in &mut sweep {
+            for _ in 0..settled_at {
+                particle.tick().unwrap();
+            }
+        }
+        assert_eq!(sweep, expected);
+    }
+
+    #[test]
+    fn engines_agree_on_puzzle_input() {
+        assert_engines_agree(&load_input().unwrap());
+    }
+
+    #[test]
+    fn engines_agree_on_generated_swarm() {
+        let generator = Generator::new(611)
+            .count(500)
+            .pos_range(-100..=100)
+            .vel_range(-5..=5)
+            .accel_range(-2..=2)
+            .collision(1, 2)
+            .collision(10, 3)
+            .collision(30, 4);
+        assert_engines_agree(&generator.generate());
+    }
+}
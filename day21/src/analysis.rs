@@ -0,0 +1,428 @@
+//! How 3x3 tiles turn into each other.
+//!
+//! With rules that split grids like the puzzle's do, three iterations turn a
+//! 3x3 tile into nine 3x3 tiles that from then on evolve independently, and
+//! rules match a tile exactly when they match every tile equivalent to it.
+//! So the future of a grid is decided by the canonical forms of its 3x3 tiles,
+//! and the graph from each canonical tile to the nine it becomes describes the
+//! whole of a rule book's long-run behaviour.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    io::{self, Write},
+};
+
+use crate::{count::Expansion, EnhanceError, Grid, RuleBook, Tile};
+
+/// Why a rule book or start pattern couldn't be analyzed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// The rule book doesn't split grids like the puzzle's, so 3x3 tiles don't
+    /// evolve independently.
+    UnsupportedSplit,
+    /// The start pattern couldn't be enhanced into 3x3 tiles.
+    Enhance(EnhanceError),
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::UnsupportedSplit => write!(
+                f,
+                "only rule books that split into 2x2 blocks and then 3x3 ones can be analyzed"
+            ),
+            AnalysisError::Enhance(err) => write!(f, "couldn't enhance the start: {}", err),
+        }
+    }
+}
+
+impl Error for AnalysisError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AnalysisError::UnsupportedSplit => None,
+            AnalysisError::Enhance(err) => Some(err),
+        }
+    }
+}
+
+impl From<EnhanceError> for AnalysisError {
+    fn from(err: EnhanceError) -> Self {
+        AnalysisError::Enhance(err)
+    }
+}
+
+/// The directed graph from each canonical 3x3 tile to the nine canonical 3x3
+/// tiles it becomes after three iterations.
+pub struct EvolutionGraph {
+    /// The canonical tiles, sorted.
+    tiles: Vec<Tile>,
+    /// The indices of the tiles each tile becomes, row by row.
+    children: Vec<[usize; 9]>,
+}
+
+impl EvolutionGraph {
+    /// The graph of every 3x3 tile that is canonical under the rule book's
+    /// symmetry.
+    pub fn new(rules: &RuleBook) -> Result<Self, AnalysisError> {
+        let symmetry = rules.symmetry();
+        let tiles = (0..1 << 9)
+            .map(|bits| Tile { side: 3, bits })
+            .filter(|&tile| tile.canonical(symmetry) == tile);
+
+        Self::closure(tiles, rules)
+    }
+
+    /// The graph of the tiles the grid grown from `start` ever contains, from
+    /// the first iteration it splits into independent 3x3 tiles on.
+    pub fn from_start(start: Tile, rules: &RuleBook) -> Result<Self, AnalysisError> {
+        if !rules.splits_like_puzzle() {
+            return Err(AnalysisError::UnsupportedSplit);
+        }
+
+        let mut grid = Grid::from(start);
+        while grid.side() % 6 != 3 {
+            grid.enhance(rules)?;
+        }
+
+        let tiles = (0..grid.side())
+            .step_by(3)
+            .flat_map(|top| (0..grid.side()).step_by(3).map(move |left| (top, left)))
+            .map(|(top, left)| grid.block(top, left, 3).canonical(rules.symmetry()))
+            .collect::<Vec<_>>();
+
+        Self::closure(tiles, rules)
+    }
+
+    /// The graph of `roots` and every tile reachable from them.
+    fn closure(
+        roots: impl IntoIterator<Item = Tile>,
+        rules: &RuleBook,
+    ) -> Result<Self, AnalysisError> {
+        if !rules.splits_like_puzzle() {
+            return Err(AnalysisError::UnsupportedSplit);
+        }
+
+        let symmetry = rules.symmetry();
+        let mut children = HashMap::new();
+        let mut queue = roots.into_iter().collect::<VecDeque<_>>();
+
+        while let Some(tile) = queue.pop_front() {
+            if children.contains_key(&tile.bits) {
+                continue;
+            }
+
+            let expansion = Expansion::new(tile.bits, rules);
+            let canonical = expansion
+                .children
+                .map(|bits| Tile { side: 3, bits }.canonical(symmetry));
+            queue.extend(canonical.iter().copied());
+            children.insert(tile.bits, canonical);
+        }
+
+        let mut tiles = children
+            .keys()
+            .map(|&bits| Tile { side: 3, bits })
+            .collect::<Vec<_>>();
+        tiles.sort_by_key(|tile| tile.bits);
+
+        let index = |tile: Tile| tiles.binary_search(&tile).unwrap();
+        let children = tiles
+            .iter()
+            .map(|tile| children[&tile.bits].map(index))
+            .collect();
+
+        Ok(Self { tiles, children })
+    }
+
+    /// Every tile in the graph, sorted.
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    /// The nine tiles `tile` becomes, row by row, or `None` if it isn't in
+    /// the graph.
+    pub fn children(&self, tile: Tile) -> Option<[Tile; 9]> {
+        let index = self.tiles.binary_search(&tile).ok()?;
+        Some(self.children[index].map(|child| self.tiles[child]))
+    }
+
+    /// The strongly connected components, each sorted, in topological order:
+    /// no tile leads to a component that comes before its own.
+    pub fn components(&self) -> Vec<Vec<Tile>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: vec![None; self.tiles.len()],
+            low_link: vec![0; self.tiles.len()],
+            stack: Vec::new(),
+            on_stack: vec![false; self.tiles.len()],
+            next_index: 0,
+            components: Vec::new(),
+        };
+
+        for node in 0..self.tiles.len() {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        }
+
+        // Tarjan's algorithm finds components after every component they lead to.
+        tarjan
+            .components
+            .into_iter()
+            .rev()
+            .map(|mut component| {
+                component.sort_unstable();
+                component.into_iter().map(|node| self.tiles[node]).collect()
+            })
+            .collect()
+    }
+
+    /// The tiles that are among the nine tiles they become.
+    pub fn fixed_points(&self) -> Vec<Tile> {
+        (0..self.tiles.len())
+            .filter(|&node| self.children[node].contains(&node))
+            .map(|node| self.tiles[node])
+            .collect()
+    }
+
+    /// For each component of more than one tile, a shortest cycle of at least
+    /// two tiles through its first tile, starting with that tile. Every tile in
+    /// such a component is on some cycle, while cycles of a single tile are the
+    /// fixed points.
+    pub fn cycles(&self) -> Vec<Vec<Tile>> {
+        let index = |tile: &Tile| self.tiles.binary_search(tile).unwrap();
+
+        self.components()
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|component| {
+                let start = index(&component[0]);
+                let mut previous = vec![None; self.tiles.len()];
+                let mut queue = VecDeque::from(vec![start]);
+
+                // Search the component breadth-first until an edge leads back
+                // to the start, ignoring the start's edge to itself if it's a
+                // fixed point.
+                let last = 'search: loop {
+                    let node = queue.pop_front().unwrap();
+                    for &child in &self.children[node] {
+                        if child == start {
+                            if node == start {
+                                continue;
+                            }
+                            break 'search node;
+                        }
+
+                        if previous[child].is_none() && component.contains(&self.tiles[child]) {
+                            previous[child] = Some(node);
+                            queue.push_back(child);
+                        }
+                    }
+                };
+
+                let mut cycle = vec![self.tiles[last]];
+                let mut node = last;
+                while let Some(parent) = previous[node] {
+                    cycle.push(self.tiles[parent]);
+                    node = parent;
+                }
+                cycle.reverse();
+                cycle
+            })
+            .collect()
+    }
+
+    /// Writes the graph in Graphviz's DOT language. Each tile is drawn as its
+    /// cells, fixed points with a double border, and edges are labelled with
+    /// how many of the nine tiles they stand for when that's more than one.
+    pub fn write_dot(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(out, "digraph evolution {{")?;
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+
+        for (node, tile) in self.tiles.iter().enumerate() {
+            let label = format!("{:#}", tile).replace('\n', "\\n");
+            let peripheries = if self.children[node].contains(&node) {
+                2
+            } else {
+                1
+            };
+            writeln!(
+                out,
+                "    \"{}\" [label=\"{}\", peripheries={}];",
+                tile, label, peripheries
+            )?;
+        }
+
+        for (node, tile) in self.tiles.iter().enumerate() {
+            let mut counts = self.children[node].to_vec();
+            counts.sort_unstable();
+
+            for group in counts.chunk_by(|a, b| a == b) {
+                write!(out, "    \"{}\" -> \"{}\"", tile, self.tiles[group[0]])?;
+                if group.len() > 1 {
+                    write!(out, " [label=\"{}\"]", group.len())?;
+                }
+                writeln!(out, ";")?;
+            }
+        }
+
+        writeln!(out, "}}")?;
+        out.flush()
+    }
+}
+
+struct Tarjan<'a> {
+    graph: &'a EvolutionGraph,
+    index: Vec<Option<usize>>,
+    low_link: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.low_link[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &child in &self.graph.children[node] {
+            match self.index[child] {
+                None => {
+                    self.visit(child);
+                    self.low_link[node] = self.low_link[node].min(self.low_link[child]);
+                }
+                Some(index) if self.on_stack[child] => {
+                    self.low_link[node] = self.low_link[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low_link[node]) == self.index[node] {
+            let mut component = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write as _;
+
+    use super::*;
+    use crate::Symmetry;
+
+    const DARK: &str = ".../.../...";
+    const CORNER: &str = ".../.../..#";
+    const PLUS: &str = ".#./###/.#.";
+
+    fn tile(pattern: &str) -> Tile {
+        pattern.parse().unwrap()
+    }
+
+    /// A rule book in which the dark tile becomes itself eight times and the
+    /// corner once, and every other tile becomes nine dark ones.
+    fn rules() -> RuleBook {
+        let mut rules = String::new();
+        for bits in 0..1 << 4 {
+            let pattern = Tile { side: 2, bits };
+            if pattern.canonical(Symmetry::D4) == pattern {
+                let output = if bits == 0 { DARK } else { "#../.../..." };
+                writeln!(rules, "{} => {}", pattern, output).unwrap();
+            }
+        }
+        for bits in 0..1 << 9 {
+            let pattern = Tile { side: 3, bits };
+            if pattern.canonical(Symmetry::D4) == pattern {
+                let output = if bits == 0 {
+                    "#.../..../..../...."
+                } else {
+                    "..../..../..../...."
+                };
+                writeln!(rules, "{} => {}", pattern, output).unwrap();
+            }
+        }
+        RuleBook::parse(&rules).unwrap()
+    }
+
+    fn graph() -> EvolutionGraph {
+        EvolutionGraph::from_start(tile(PLUS), &rules()).unwrap()
+    }
+
+    #[test]
+    fn follows_the_rules() {
+        let graph = graph();
+        assert_eq!(graph.tiles(), [tile(DARK), tile(CORNER), tile(PLUS)]);
+
+        let mut dark = [tile(DARK); 9];
+        dark[0] = tile(CORNER);
+        assert_eq!(graph.children(tile(DARK)), Some(dark));
+        assert_eq!(graph.children(tile(CORNER)), Some([tile(DARK); 9]));
+        assert_eq!(graph.children(tile(PLUS)), Some([tile(DARK); 9]));
+        assert_eq!(graph.children(tile("#../.../...")), None);
+
+        assert_eq!(EvolutionGraph::new(&rules()).unwrap().tiles().len(), 102);
+    }
+
+    #[test]
+    fn finds_components_fixed_points_and_cycles() {
+        let graph = graph();
+        assert_eq!(
+            graph.components(),
+            [vec![tile(PLUS)], vec![tile(DARK), tile(CORNER)]]
+        );
+        assert_eq!(graph.fixed_points(), [tile(DARK)]);
+        // The dark tile becoming itself mustn't pass for the cycle.
+        assert_eq!(graph.cycles(), [vec![tile(DARK), tile(CORNER)]]);
+    }
+
+    #[test]
+    fn puzzle_cycles_are_real() {
+        let rules = RuleBook::parse(include_str!("input.txt")).unwrap();
+        let graph = EvolutionGraph::new(&rules).unwrap();
+
+        let cycles = graph.cycles();
+        assert!(!cycles.is_empty());
+        for cycle in cycles {
+            assert!(cycle.len() >= 2, "{:?}", cycle);
+            for (i, tile) in cycle.iter().enumerate() {
+                let next = cycle[(i + 1) % cycle.len()];
+                assert!(graph.children(*tile).unwrap().contains(&next));
+            }
+        }
+    }
+
+    #[test]
+    fn writes_dot() {
+        let mut dot = Vec::new();
+        graph().write_dot(&mut dot).unwrap();
+        assert_eq!(
+            String::from_utf8(dot).unwrap(),
+            r#"digraph evolution {
+    node [shape=box, fontname="monospace"];
+    ".../.../..." [label="...\n...\n...", peripheries=2];
+    ".../.../..#" [label="...\n...\n..#", peripheries=1];
+    ".#./###/.#." [label=".#.\n###\n.#.", peripheries=1];
+    ".../.../..." -> ".../.../..." [label="8"];
+    ".../.../..." -> ".../.../..#";
+    ".../.../..#" -> ".../.../..." [label="9"];
+    ".#./###/.#." -> ".../.../..." [label="9"];
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::{
+    grid::{block, enhance},
+    Engine, EnhanceError, LitCount, RuleBook, Tile,
+};
+
+/// What becomes of a 3x3 block over the next three iterations.
+///
+/// Three iterations turn a 3x3 block into a 4x4, then a 6x6 and then a 9x9
+/// one, and every split along the way stays inside the block. The 9x9 result
+/// is made of nine 3x3 blocks which from then on evolve independently.
+#[derive(Clone, Copy)]
+pub(crate) struct Expansion {
+    /// The lit cells after zero, one and two iterations.
+    lit: [u64; 3],
+    /// The 3x3 blocks after three iterations, row by row.
+    pub(crate) children: [u64; 9],
+}
+
+impl Expansion {
+    /// Expands the 3x3 block `bits`, which `rules` must split like the
+    /// puzzle's do.
+    pub(crate) fn new(bits: u64, rules: &RuleBook) -> Self {
+        let mut cells = Tile { side: 3, bits }.cells();
+        let mut side = 3;
+        let mut next_cells = Vec::new();
+
+        let mut lit = [0; 3];
+        for lit in &mut lit {
+            *lit = cells.iter().filter(|&&cell| cell).count() as u64;
+            enhance(
+                &mut cells,
+                &mut side,
+                &mut next_cells,
+                rules,
+                Engine::Sequential,
+            )
+            .expect("rule books that split like the puzzle's cover every 2x2 and 3x3 block");
+        }
+
+        let mut children = [0; 9];
+        for (i, child) in children.iter_mut().enumerate() {
+            *child = block(&cells, side, i / 3 * 3, i % 3 * 3, 3);
+        }
+
+        Self { lit, children }
+    }
+}
+
+/// Like [`count_after`], but returns an error instead of panicking.
+pub fn checked_count_after(
+    iterations: usize,
+    start: Tile,
+    rules: &RuleBook,
+) -> Result<LitCount, EnhanceError> {
+    let mut cells = start.cells();
+    let mut side = start.side;
+    let mut next_cells = Vec::new();
+    let mut done = 0;
+
+    // Blocks only evolve independently once the grid is split into 3x3 ones
+    // and will be again three iterations later, which with the puzzle's split
+    // rules holds for any side that is an odd multiple of three.
+    while !(rules.splits_like_puzzle() && side % 6 == 3) {
+        if done == iterations {
+            return Ok(LitCount::from(
+                cells.iter().filter(|&&cell| cell).count() as u64
+            ));
+        }
+
+        enhance(
+            &mut cells,
+            &mut side,
+            &mut next_cells,
+            rules,
+            Engine::Sequential,
+        )?;
+        done += 1;
+    }
+
+    let mut counts = HashMap::new();
+    for top in (0..side).step_by(3) {
+        for left in (0..side).step_by(3) {
+            *counts
+                .entry(block(&cells, side, top, left, 3))
+                .or_insert(0u64) += 1;
+        }
+    }
+
+    let mut expansions = HashMap::new();
+    let mut expansion = |bits| {
+        *expansions
+            .entry(bits)
+            .or_insert_with(|| Expansion::new(bits, rules))
+    };
+
+    let mut counts = counts
+        .into_iter()
+        .map(|(bits, count)| (bits, LitCount::from(count)))
+        .collect::<HashMap<_, _>>();
+
+    for _ in 0..(iterations - done) / 3 {
+        let mut next_counts = HashMap::<_, LitCount>::new();
+
+        for (bits, count) in counts {
+            for &child in &expansion(bits).children {
+                next_counts.entry(child).or_default().add_assign(&count);
+            }
+        }
+
+        counts = next_counts;
+    }
+
+    let remainder = (iterations - done) % 3;
+    let mut total = LitCount::default();
+    for (bits, count) in counts {
+        total.add_assign(&count.mul(expansion(bits).lit[remainder]));
+    }
+    Ok(total)
+}
+
+/// Counts the lit cells after `iterations` enhancements of `start`, without
+/// building the grid when `rules` split it like the puzzle's do.
+///
+/// The grid is then tracked as the number of copies of each distinct 3x3
+/// block, so the work done grows with the number of distinct blocks rather
+/// than with the size of the grid. Other rule books fall back to enhancing the
+/// whole grid.
+///
+/// # Panics
+///
+/// Panics if the grid can't be enhanced.
+pub fn count_after(iterations: usize, start: Tile, rules: &RuleBook) -> LitCount {
+    checked_count_after(iterations, start, rules).unwrap_or_else(|err| panic!("{}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solve, START};
+
+    fn count(iterations: usize) -> LitCount {
+        let rules = RuleBook::parse(include_str!("input.txt")).unwrap();
+        count_after(iterations, START.parse().unwrap(), &rules)
+    }
+
+    #[test]
+    fn agrees_with_solve() {
+        let (part1, part2) = solve();
+        assert_eq!(count(5), LitCount::from(part1 as u64));
+        assert_eq!(count(18), LitCount::from(part2 as u64));
+    }
+
+    #[test]
+    fn counts_past_any_primitive_integer() {
+        let count = count(1000);
+        assert_eq!(count.to_u128(), None);
+        // Every three iterations triple the grid's side, so after a thousand
+        // it has nearly 10^319 cells, a good share of them lit.
+        assert_eq!(count.to_string().len(), 319);
+    }
+}
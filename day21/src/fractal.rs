@@ -0,0 +1,129 @@
+use std::{error::Error, fmt};
+
+use crate::{Engine, EnhanceError, Grid, PatternError, RuleBook, RuleError, Symmetry, Tile, START};
+
+/// Why a [`Fractal`] couldn't be built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FractalError {
+    Start(PatternError),
+    Rules(Vec<RuleError>),
+}
+
+impl fmt::Display for FractalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FractalError::Start(err) => write!(f, "invalid start pattern: {}", err),
+            FractalError::Rules(errors) => {
+                write!(f, "invalid rule book")?;
+                for err in errors {
+                    write!(f, "\n  {}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for FractalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FractalError::Start(err) => Some(err),
+            FractalError::Rules(_) => None,
+        }
+    }
+}
+
+/// Sets up a [`Fractal`], by default with the puzzle's start pattern and
+/// rules.
+#[derive(Debug, Clone, Copy)]
+pub struct FractalBuilder<'a> {
+    start: &'a str,
+    rules: &'a str,
+    symmetry: Symmetry,
+    engine: Engine,
+}
+
+impl<'a> FractalBuilder<'a> {
+    /// The pattern to start from, in the `.#./..#/###` notation.
+    pub fn start(mut self, start: &'a str) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// The rule book, in the format [`RuleBook::parse`] accepts.
+    pub fn rules(mut self, rules: &'a str) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// How rules match patterns other than their input.
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// How each step replaces the blocks of the grid.
+    pub fn engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    pub fn build(self) -> Result<Fractal, FractalError> {
+        let start = self.start.parse::<Tile>().map_err(FractalError::Start)?;
+        let rules = RuleBook::parse_with(self.rules, self.symmetry).map_err(FractalError::Rules)?;
+
+        Ok(Fractal {
+            rules,
+            grid: Grid::from(start),
+            scratch: Vec::new(),
+            engine: self.engine,
+            iterations: 0,
+        })
+    }
+}
+
+/// A start pattern being enhanced over and over by a rule book.
+pub struct Fractal {
+    rules: RuleBook,
+    grid: Grid,
+    scratch: Vec<bool>,
+    engine: Engine,
+    iterations: usize,
+}
+
+impl Fractal {
+    pub fn builder() -> FractalBuilder<'static> {
+        FractalBuilder {
+            start: START,
+            rules: include_str!("input.txt"),
+            symmetry: Symmetry::default(),
+            engine: Engine::Sequential,
+        }
+    }
+
+    /// Enhances the grid once, leaving it as it was if that fails.
+    pub fn step(&mut self) -> Result<(), EnhanceError> {
+        self.grid
+            .enhance_reusing(&self.rules, self.engine, &mut self.scratch)?;
+        self.iterations += 1;
+        Ok(())
+    }
+
+    /// How many times the grid has been enhanced.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// The number of lit cells in the grid.
+    pub fn lit_count(&self) -> usize {
+        self.grid.lit_count()
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn rules(&self) -> &RuleBook {
+        &self.rules
+    }
+}
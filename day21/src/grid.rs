@@ -0,0 +1,282 @@
+use std::{error::Error, fmt};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{
+    rules::{RuleBook, Table},
+    tile, Tile,
+};
+
+/// How `Grid::enhance_with` replaces the blocks of a grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Fill one band of blocks after the other on the current thread.
+    Sequential,
+    /// Fill bands of blocks across threads with rayon. Gives exactly the same
+    /// grid, or the same error, as `Sequential`.
+    #[cfg(feature = "parallel")]
+    Parallel,
+}
+
+/// Why a grid couldn't be enhanced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnhanceError {
+    /// None of the rule book's block sizes divides the side of the grid.
+    Indivisible { side: usize },
+    /// No rule matches this block. The rule book only checks up front that
+    /// blocks of up to 3x3 are all covered, so this can only happen for larger
+    /// blocks.
+    Uncovered(Tile),
+}
+
+impl fmt::Display for EnhanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnhanceError::Indivisible { side } => write!(
+                f,
+                "no block size of the rule book divides the grid's side of {}",
+                side
+            ),
+            EnhanceError::Uncovered(tile) => write!(f, "no rule matches the block `{}`", tile),
+        }
+    }
+}
+
+impl Error for EnhanceError {}
+
+/// The bits of the `size`x`size` block whose top-left cell is at `(top, left)`.
+pub(crate) fn block(cells: &[bool], side: usize, top: usize, left: usize, size: usize) -> u64 {
+    let mut bits = 0;
+    for row in top..top + size {
+        let start = row * side + left;
+        for &cell in &cells[start..start + size] {
+            bits = bits << 1 | u64::from(cell);
+        }
+    }
+    bits
+}
+
+/// Fills `band` with the outputs of the `block_row`th row of blocks. Inlined
+/// into each call so that the puzzle's block sizes become constants.
+#[inline(always)]
+fn fill_band(
+    cells: &[bool],
+    side: usize,
+    band: &mut [bool],
+    block_row: usize,
+    table: &Table,
+    from: usize,
+    to: usize,
+) -> Result<(), EnhanceError> {
+    let next_side = side / from * to;
+
+    for block_col in 0..side / from {
+        let bits = block(cells, side, block_row * from, block_col * from, from);
+        let replacement = table
+            .get(bits)
+            .ok_or(EnhanceError::Uncovered(Tile { side: from, bits }))?;
+
+        let mut shift = to * to;
+        for row in 0..to {
+            let start = row * next_side + block_col * to;
+            for cell in &mut band[start..start + to] {
+                shift -= 1;
+                *cell = replacement >> shift & 1 == 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the `block_row`th row of blocks of `cells`, writing the result
+/// to `band`.
+fn replace_band(
+    cells: &[bool],
+    side: usize,
+    band: &mut [bool],
+    block_row: usize,
+    table: &Table,
+) -> Result<(), EnhanceError> {
+    match (table.from, table.to) {
+        (2, 3) => fill_band(cells, side, band, block_row, table, 2, 3),
+        (3, 4) => fill_band(cells, side, band, block_row, table, 3, 4),
+        (from, to) => fill_band(cells, side, band, block_row, table, from, to),
+    }
+}
+
+/// Replaces each block of `cells`, writing the result to `next_cells`.
+fn replace_blocks(
+    cells: &[bool],
+    side: usize,
+    next_cells: &mut [bool],
+    table: &Table,
+) -> Result<(), EnhanceError> {
+    let band = side / table.from * table.to * table.to;
+
+    for (block_row, band) in next_cells.chunks_mut(band).enumerate() {
+        replace_band(cells, side, band, block_row, table)?;
+    }
+
+    Ok(())
+}
+
+/// Roughly how many output cells a thread fills at a time, since smaller
+/// tasks cost more to hand out than to do.
+#[cfg(feature = "parallel")]
+const MIN_CELLS_PER_TASK: usize = 1 << 16;
+
+/// Replaces each block of `cells`, writing the result to `next_cells` with
+/// each band of rows filled on its own thread. If some blocks have no rule,
+/// the first one in the grid is reported, just like sequentially.
+#[cfg(feature = "parallel")]
+fn par_replace_blocks(
+    cells: &[bool],
+    side: usize,
+    next_cells: &mut [bool],
+    table: &Table,
+) -> Result<(), EnhanceError> {
+    let band = side / table.from * table.to * table.to;
+
+    next_cells
+        .par_chunks_mut(band)
+        .enumerate()
+        .with_min_len(MIN_CELLS_PER_TASK / band)
+        .filter_map(|(block_row, band)| {
+            replace_band(cells, side, band, block_row, table)
+                .err()
+                .map(|err| (block_row, err))
+        })
+        .min_by_key(|&(block_row, _)| block_row)
+        .map_or(Ok(()), |(_, err)| Err(err))
+}
+
+pub(crate) fn enhance(
+    cells: &mut Vec<bool>,
+    side: &mut usize,
+    next_cells: &mut Vec<bool>,
+    rules: &RuleBook,
+    engine: Engine,
+) -> Result<(), EnhanceError> {
+    let table = rules
+        .split(*side)
+        .ok_or(EnhanceError::Indivisible { side: *side })?;
+    let next_side = *side / table.from * table.to;
+
+    next_cells.clear();
+    next_cells.resize(next_side * next_side, false);
+    match engine {
+        Engine::Sequential => replace_blocks(cells, *side, next_cells, table)?,
+        #[cfg(feature = "parallel")]
+        Engine::Parallel => par_replace_blocks(cells, *side, next_cells, table)?,
+    }
+
+    std::mem::swap(cells, next_cells);
+    *side = next_side;
+    Ok(())
+}
+
+/// The whole picture after some number of enhancements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    side: usize,
+    cells: Vec<bool>,
+}
+
+impl From<Tile> for Grid {
+    fn from(tile: Tile) -> Self {
+        Self {
+            side: tile.side,
+            cells: tile.cells(),
+        }
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        tile::write_cells(f, self.side, |row, col| self.cell(row, col))
+    }
+}
+
+impl Grid {
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    /// Whether the cell at `(row, col)` is lit, which `Display` shows as `#`.
+    pub fn cell(&self, row: usize, col: usize) -> bool {
+        self.cells[row * self.side + col]
+    }
+
+    pub fn lit_count(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell).count()
+    }
+
+    /// The `size`x`size` block whose top-left cell is at `(top, left)`.
+    pub(crate) fn block(&self, top: usize, left: usize, size: usize) -> Tile {
+        Tile {
+            side: size,
+            bits: block(&self.cells, self.side, top, left, size),
+        }
+    }
+
+    /// Replaces every block of the grid according to `rules`, leaving the
+    /// grid as it was if that fails.
+    pub fn enhance(&mut self, rules: &RuleBook) -> Result<(), EnhanceError> {
+        self.enhance_with(rules, Engine::Sequential)
+    }
+
+    /// Like `enhance`, but replaces the blocks using `engine`.
+    pub fn enhance_with(&mut self, rules: &RuleBook, engine: Engine) -> Result<(), EnhanceError> {
+        self.enhance_reusing(rules, engine, &mut Vec::new())
+    }
+
+    /// Like `enhance_with`, but builds the new cells in `scratch`, leaving the
+    /// old ones there in exchange.
+    pub(crate) fn enhance_reusing(
+        &mut self,
+        rules: &RuleBook,
+        engine: Engine,
+        scratch: &mut Vec<bool>,
+    ) -> Result<(), EnhanceError> {
+        enhance(&mut self.cells, &mut self.side, scratch, rules, engine)
+    }
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::*;
+    use crate::START;
+
+    fn assert_engines_agree(start: &str, rules: &RuleBook, iterations: usize) {
+        let mut sequential = Grid::from(start.parse::<Tile>().unwrap());
+        let mut parallel = sequential.clone();
+
+        for _ in 0..iterations {
+            let expected = sequential.enhance_with(rules, Engine::Sequential);
+            assert_eq!(parallel.enhance_with(rules, Engine::Parallel), expected);
+            assert_eq!(parallel, sequential);
+        }
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let rules = RuleBook::parse(include_str!("input.txt")).unwrap();
+        assert_engines_agree(START, &rules, 18);
+    }
+
+    #[test]
+    fn parallel_reports_the_same_uncovered_block() {
+        let rules = RuleBook::parse(
+            "..../..../..../.... => ...../...../...../...../.....
+             #.../..../..../.... => #..../...../...../...../.....",
+        )
+        .unwrap();
+        assert_engines_agree(
+            "#......./......../......../......../......#./......../......../##......",
+            &rules,
+            1,
+        );
+    }
+}
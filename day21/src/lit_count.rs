@@ -0,0 +1,149 @@
+use std::fmt;
+
+/// A count of lit cells, which after a few hundred iterations is far too big
+/// for any primitive integer.
+///
+/// Stored as base 2^64 digits, least significant first, without trailing
+/// zeros so that equal counts compare equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LitCount {
+    digits: Vec<u64>,
+}
+
+impl LitCount {
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    /// The count as a `u128`, if it fits in one.
+    pub fn to_u128(&self) -> Option<u128> {
+        match self.digits[..] {
+            [] => Some(0),
+            [low] => Some(low.into()),
+            [low, high] => Some(u128::from(high) << 64 | u128::from(low)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn add_assign(&mut self, other: &Self) {
+        if self.digits.len() < other.digits.len() {
+            self.digits.resize(other.digits.len(), 0);
+        }
+
+        let mut carry = false;
+        for (i, digit) in self.digits.iter_mut().enumerate() {
+            if i >= other.digits.len() && !carry {
+                break;
+            }
+            let rhs = other.digits.get(i).copied().unwrap_or(0);
+            let (sum, overflowed) = digit.overflowing_add(rhs);
+            let (sum, carried) = sum.overflowing_add(carry.into());
+            *digit = sum;
+            carry = overflowed || carried;
+        }
+
+        if carry {
+            self.digits.push(1);
+        }
+    }
+
+    pub(crate) fn mul(&self, factor: u64) -> Self {
+        if factor == 0 {
+            return Self::default();
+        }
+
+        let mut carry = 0;
+        let mut digits = self
+            .digits
+            .iter()
+            .map(|&digit| {
+                let product = u128::from(digit) * u128::from(factor) + carry;
+                carry = product >> 64;
+                product as u64
+            })
+            .collect::<Vec<_>>();
+
+        if carry != 0 {
+            digits.push(carry as u64);
+        }
+
+        Self { digits }
+    }
+
+    /// Divides in place by `divisor` and returns the remainder.
+    fn div_rem(&mut self, divisor: u64) -> u64 {
+        let mut remainder = 0;
+        for digit in self.digits.iter_mut().rev() {
+            let dividend = u128::from(remainder) << 64 | u128::from(*digit);
+            *digit = (dividend / u128::from(divisor)) as u64;
+            remainder = (dividend % u128::from(divisor)) as u64;
+        }
+
+        while self.digits.last() == Some(&0) {
+            self.digits.pop();
+        }
+
+        remainder
+    }
+}
+
+impl From<u64> for LitCount {
+    fn from(count: u64) -> Self {
+        Self::from(u128::from(count))
+    }
+}
+
+impl From<u128> for LitCount {
+    fn from(count: u128) -> Self {
+        let mut digits = vec![count as u64, (count >> 64) as u64];
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self { digits }
+    }
+}
+
+impl fmt::Display for LitCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut rest = self.clone();
+        let mut chunks = Vec::new();
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem(CHUNK));
+        }
+
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap_or(0))?;
+        chunks.try_for_each(|chunk| write!(f, "{:019}", chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_carries_across_digits() {
+        let mut count = LitCount::from(u128::MAX);
+        count.add_assign(&LitCount::from(1u64));
+        assert_eq!(count.to_u128(), None);
+        assert_eq!(count.to_string(), "340282366920938463463374607431768211456");
+
+        let count = LitCount::from(u64::MAX).mul(u64::MAX);
+        assert_eq!(
+            count.to_u128(),
+            Some(u128::from(u64::MAX) * u128::from(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn displays_in_decimal() {
+        assert_eq!(LitCount::default().to_string(), "0");
+        assert_eq!(LitCount::from(12u64).to_string(), "12");
+        assert_eq!(
+            LitCount::from(10_000_000_000_000_000_007u128).to_string(),
+            "10000000000000000007"
+        );
+    }
+}
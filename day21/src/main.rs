@@ -1,5 +1,245 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    error::Error,
+    fs::{self, File},
+    io::BufWriter,
+    path::Path,
+    process, slice,
+};
+
+use day21::{Bitmap, EvolutionGraph, Fractal, Symmetry, Tile};
+
+const USAGE: &str = "usage: day21 [SETUP] [--report N,N...]
+       day21 render [SETUP] [--iterations N] [--scale N] [--format pbm | png] [--sheet]
+                    [--out PATH]
+       day21 analyze [SETUP] [--all] [--dot PATH]
+where SETUP is [--start PATTERN] [--rules PATH] [--symmetry d4 | c4 | none]";
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("day21: {}", message);
+    process::exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn parse_number(value: &str) -> usize {
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(format!("invalid number `{}`", value)))
+}
+
+fn next_number(args: &mut slice::Iter<&str>) -> usize {
+    parse_number(args.next().unwrap_or_else(|| usage()))
+}
+
+/// The options every mode takes to set up the fractal.
+#[derive(Default)]
+struct Setup<'a> {
+    start: Option<&'a str>,
+    rules: Option<&'a str>,
+    symmetry: Symmetry,
+}
+
+impl<'a> Setup<'a> {
+    /// Takes `arg` and its value if it's one of these options.
+    fn accept(&mut self, arg: &str, args: &mut slice::Iter<&'a str>) -> bool {
+        let mut value = || *args.next().unwrap_or_else(|| usage());
+
+        match arg {
+            "--start" => self.start = Some(value()),
+            "--rules" => self.rules = Some(value()),
+            "--symmetry" => {
+                self.symmetry = match value() {
+                    "d4" => Symmetry::D4,
+                    "c4" => Symmetry::C4,
+                    "none" => Symmetry::None,
+                    other => fail(format!("unknown symmetry `{}`", other)),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Builds the fractal, reading the rules from their path if one was given
+    /// and otherwise using the puzzle's.
+    fn build(&self) -> Fractal {
+        let rules = self.rules.map(|path| {
+            fs::read_to_string(path)
+                .unwrap_or_else(|err| fail(format!("couldn't read `{}`: {}", path, err)))
+        });
+
+        let mut builder = Fractal::builder().symmetry(self.symmetry);
+        if let Some(start) = self.start {
+            builder = builder.start(start);
+        }
+        if let Some(rules) = &rules {
+            builder = builder.rules(rules);
+        }
+        builder.build().unwrap_or_else(|err| fail(err))
+    }
+}
+
+fn write(bitmap: &Bitmap, format: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let file = BufWriter::new(File::create(path)?);
+    match format {
+        #[cfg(feature = "png")]
+        "png" => bitmap.write_png(file)?,
+        _ => bitmap.write_pbm(file)?,
+    }
+    Ok(())
+}
+
+/// Draws the grid after each of the first iterations, either as one image per
+/// iteration or side by side in a single image.
+fn render(args: &[&str]) {
+    let mut args = args.iter();
+    let mut setup = Setup::default();
+    let mut iterations = 5;
+    let mut scale = 4;
+    let mut format = "pbm";
+    let mut sheet = false;
+    let mut out = None;
+
+    while let Some(&arg) = args.next() {
+        match arg {
+            _ if setup.accept(arg, &mut args) => {}
+            "--iterations" => iterations = next_number(&mut args),
+            "--scale" => scale = next_number(&mut args).max(1),
+            "--format" => match args.next() {
+                Some(&"pbm") => format = "pbm",
+                Some(&"png") if cfg!(feature = "png") => format = "png",
+                Some(&"png") => fail("PNG output needs the `png` feature"),
+                Some(value) => fail(format!("unknown format `{}`", value)),
+                None => usage(),
+            },
+            "--sheet" => sheet = true,
+            "--out" => out = Some(*args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+
+    let mut fractal = setup.build();
+    let mut grids = vec![fractal.grid().clone()];
+    for _ in 0..iterations {
+        fractal.step().unwrap_or_else(|err| fail(err));
+        grids.push(fractal.grid().clone());
+    }
+
+    let result = if sheet {
+        let path = out.map_or_else(|| format!("sheet.{}", format), String::from);
+        write(
+            &Bitmap::contact_sheet(&grids, scale),
+            format,
+            Path::new(&path),
+        )
+    } else {
+        let dir = Path::new(out.unwrap_or("frames"));
+        fs::create_dir_all(dir).map_err(Box::from).and_then(|()| {
+            for (i, grid) in grids.iter().enumerate() {
+                let path = dir.join(format!("iteration_{:02}.{}", i, format));
+                write(&Bitmap::new(grid, scale), format, &path)?;
+            }
+            Ok(())
+        })
+    };
+
+    result.unwrap_or_else(|err| fail(err));
+}
+
+/// Prints the number of lit cells after each of the iteration counts asked
+/// for, in the order they were given.
+fn report(args: &[&str]) {
+    let mut args = args.iter();
+    let mut setup = Setup::default();
+    let mut report = vec![5, 18];
+
+    while let Some(&arg) = args.next() {
+        match arg {
+            _ if setup.accept(arg, &mut args) => {}
+            "--report" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                report = value.split(',').map(parse_number).collect();
+            }
+            _ => usage(),
+        }
+    }
+
+    let mut fractal = setup.build();
+    let mut lit = BTreeMap::new();
+    for &iterations in &report {
+        lit.insert(iterations, 0);
+    }
+
+    for (&iterations, lit) in &mut lit {
+        while fractal.iterations() < iterations {
+            fractal.step().unwrap_or_else(|err| fail(err));
+        }
+        *lit = fractal.lit_count();
+    }
+
+    for iterations in report {
+        println!("{}", lit[&iterations]);
+    }
+}
+
+fn join(tiles: &[Tile], separator: &str) -> String {
+    let tiles = tiles.iter().map(Tile::to_string).collect::<Vec<_>>();
+    tiles.join(separator)
+}
+
+/// Describes how the 3x3 tiles of the grid, or all 3x3 tiles, turn into each
+/// other, optionally writing the graph out as DOT.
+fn analyze(args: &[&str]) {
+    let mut args = args.iter();
+    let mut setup = Setup::default();
+    let mut all = false;
+    let mut dot = None;
+
+    while let Some(&arg) = args.next() {
+        match arg {
+            _ if setup.accept(arg, &mut args) => {}
+            "--all" => all = true,
+            "--dot" => dot = Some(*args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+
+    let fractal = setup.build();
+    let graph = if all {
+        EvolutionGraph::new(fractal.rules())
+    } else {
+        let start = setup.start.unwrap_or(day21::START).parse().unwrap();
+        EvolutionGraph::from_start(start, fractal.rules())
+    };
+    let graph = graph.unwrap_or_else(|err| fail(err));
+
+    let components = graph.components();
+    println!("tiles: {}", graph.tiles().len());
+    println!("components: {}", components.len());
+    println!("fixed points: {}", join(&graph.fixed_points(), " "));
+    for cycle in graph.cycles() {
+        println!("cycle: {} -> {}", join(&cycle, " -> "), cycle[0]);
+    }
+
+    if let Some(path) = dot {
+        File::create(path)
+            .and_then(|file| graph.write_dot(BufWriter::new(file)))
+            .unwrap_or_else(|err| fail(err));
+    }
+}
+
 fn main() {
-    let (part1, part2) = day21::solve();
-    println!("{}", part1);
-    println!("{}", part2);
-}
\ No newline at end of file
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match args[..] {
+        ["render", ref rest @ ..] => render(rest),
+        ["analyze", ref rest @ ..] => analyze(rest),
+        ref rest => report(rest),
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{self, Write};
+
+use crate::Grid;
+
+/// A black and white picture of one or more grids, with lit cells drawn black
+/// just like `Display` draws them as `#`, and each cell `scale` pixels wide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    /// Whether each pixel is black, row by row.
+    pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    fn blank(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    /// Draws `grid` with its top-left corner `left` pixels from the left edge.
+    fn draw(&mut self, grid: &Grid, scale: usize, left: usize) {
+        for y in 0..grid.side() * scale {
+            let start = y * self.width + left;
+            let row = &mut self.pixels[start..start + grid.side() * scale];
+
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = grid.cell(y / scale, x / scale);
+            }
+        }
+    }
+
+    pub fn new(grid: &Grid, scale: usize) -> Self {
+        let side = grid.side() * scale;
+        let mut bitmap = Self::blank(side, side);
+        bitmap.draw(grid, scale, 0);
+        bitmap
+    }
+
+    /// Lays `grids` out left to right, aligned to the top and separated by a
+    /// blank column one cell wide.
+    pub fn contact_sheet(grids: &[Grid], scale: usize) -> Self {
+        let width = grids
+            .iter()
+            .map(|grid| (grid.side() + 1) * scale)
+            .sum::<usize>();
+        let height = grids.iter().map(|grid| grid.side() * scale).max();
+
+        let mut bitmap = Self::blank(width.saturating_sub(scale), height.unwrap_or(0));
+        let mut left = 0;
+        for grid in grids {
+            bitmap.draw(grid, scale, left);
+            left += (grid.side() + 1) * scale;
+        }
+        bitmap
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the pixel `x` from the left and `y` from the top is black.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Packs each row into bytes, most significant bit first, setting the
+    /// bits of the pixels for which `set` holds and padding rows with zeros.
+    fn pack(&self, set: impl Fn(bool) -> bool) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.height * (self.width + 7) / 8);
+
+        for row in self.pixels.chunks_exact(self.width.max(1)) {
+            for chunk in row.chunks(8) {
+                let byte = chunk
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &pixel)| set(pixel))
+                    .fold(0, |byte, (i, _)| byte | 0x80 >> i);
+                bytes.push(byte);
+            }
+        }
+
+        bytes
+    }
+
+    /// Writes the bitmap as a binary Netpbm bitmap (`P4`).
+    pub fn write_pbm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P4\n{} {}\n", self.width, self.height)?;
+        out.write_all(&self.pack(|black| black))?;
+        out.flush()
+    }
+
+    /// Writes the bitmap as a 1-bit grayscale PNG.
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, out: impl Write) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::One);
+
+        // PNG grayscale stores white as 1, the opposite of PBM.
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pack(|black| !black))?;
+        writer.finish()
+    }
+}
@@ -0,0 +1,485 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt,
+};
+
+use crate::tile::{PatternError, Symmetry, Tile};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleErrorKind {
+    /// The line isn't of the form `PATTERN => PATTERN`.
+    Malformed,
+    /// The pattern left of the `=>` didn't parse.
+    InvalidInput(PatternError),
+    /// The pattern right of the `=>` didn't parse.
+    InvalidOutput(PatternError),
+    /// An earlier rule for patterns of the same size has a differently sized
+    /// output.
+    WrongOutputSize {
+        input: usize,
+        expected: usize,
+        output: usize,
+    },
+    /// The input, or a pattern the rule book's symmetry makes equivalent to
+    /// it, is matched by the rule on this line, which has a different output.
+    Conflict { line: usize },
+    /// No rule matches this pattern or any pattern equivalent to it.
+    Uncovered(Tile),
+    /// A `split:` line isn't followed by distinct, positive block sizes.
+    InvalidSplit,
+    /// The split order was already declared on this line.
+    DuplicateSplit { line: usize },
+    /// Patterns of this size have rules but are missing from the split order
+    /// declared on `split_line`.
+    UndeclaredSize { side: usize, split_line: usize },
+    /// The split order lists a block size that has no rules.
+    NoRules(usize),
+}
+
+impl fmt::Display for RuleErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleErrorKind::Malformed => write!(f, "expected `PATTERN => PATTERN`"),
+            RuleErrorKind::InvalidInput(err) => write!(f, "invalid input pattern: {}", err),
+            RuleErrorKind::InvalidOutput(err) => write!(f, "invalid output pattern: {}", err),
+            RuleErrorKind::WrongOutputSize {
+                input,
+                expected,
+                output,
+            } => write!(
+                f,
+                "earlier {0}x{0} patterns become {1}x{1} ones, not {2}x{2}",
+                input, expected, output
+            ),
+            RuleErrorKind::Conflict { line } => write!(
+                f,
+                "this pattern or an equivalent one has a different output on line {}",
+                line
+            ),
+            RuleErrorKind::Uncovered(tile) => write!(
+                f,
+                "no rule matches `{}` or any pattern equivalent to it",
+                tile
+            ),
+            RuleErrorKind::InvalidSplit => {
+                write!(f, "expected `split:` followed by distinct block sizes")
+            }
+            RuleErrorKind::DuplicateSplit { line } => {
+                write!(f, "the split order was already declared on line {}", line)
+            }
+            RuleErrorKind::UndeclaredSize { side, split_line } => write!(
+                f,
+                "{0}x{0} patterns are missing from the split order on line {1}",
+                side, split_line
+            ),
+            RuleErrorKind::NoRules(side) => {
+                write!(f, "no rules for the {0}x{0} block size", side)
+            }
+        }
+    }
+}
+
+/// A problem with a rule book. Lines start at 1, and uncovered patterns have
+/// no line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleError {
+    pub line: Option<usize>,
+    pub kind: RuleErrorKind,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl Error for RuleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            RuleErrorKind::InvalidInput(err) | RuleErrorKind::InvalidOutput(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Patterns up to this size are checked to all be covered when parsing, as
+/// every rule book needs them to enhance small grids.
+const COVERED_MAX_SIDE: usize = 3;
+
+/// Patterns up to this size are looked up in a table indexed by their bits.
+const DIRECT_MAX_SIDE: usize = 4;
+
+/// The replacements of every `from`x`from` pattern, all `to`x`to`.
+pub(crate) struct Table {
+    pub(crate) from: usize,
+    pub(crate) to: usize,
+    symmetry: Symmetry,
+    /// The output of each orbit, keyed by the bits of its canonical form.
+    orbits: HashMap<u64, u64>,
+    /// The output of every pattern, or `None` if no rule covers it, indexed
+    /// by its bits so that small patterns needn't be canonicalized to be
+    /// looked up.
+    direct: Option<Vec<Option<u64>>>,
+}
+
+impl Table {
+    /// The bits of the tile that replaces the pattern `bits`.
+    pub(crate) fn get(&self, bits: u64) -> Option<u64> {
+        match &self.direct {
+            Some(direct) => direct[bits as usize],
+            None => {
+                let tile = Tile {
+                    side: self.from,
+                    bits,
+                };
+                self.orbits
+                    .get(&tile.canonical(self.symmetry).bits)
+                    .copied()
+            }
+        }
+    }
+}
+
+/// The enhancement rules, one table per block size in the order the sizes are
+/// tried when splitting a grid.
+pub struct RuleBook {
+    symmetry: Symmetry,
+    tables: Vec<Table>,
+}
+
+/// The rules for one size of pattern while they're being parsed.
+struct Draft {
+    to: usize,
+    /// The line of the first rule.
+    line: usize,
+    /// The output of each orbit, keyed by the bits of its canonical form,
+    /// along with the line it came from.
+    orbits: HashMap<u64, (u64, usize)>,
+}
+
+fn parse_rule(rule: &str) -> Result<(Tile, Tile), RuleErrorKind> {
+    let (lhs, rhs) = rule.split_once("=>").ok_or(RuleErrorKind::Malformed)?;
+    let lhs: Tile = lhs.trim().parse().map_err(RuleErrorKind::InvalidInput)?;
+    let rhs: Tile = rhs.trim().parse().map_err(RuleErrorKind::InvalidOutput)?;
+    Ok((lhs, rhs))
+}
+
+fn parse_split(sizes: &str) -> Option<Vec<usize>> {
+    let mut seen = BTreeSet::new();
+    sizes
+        .split(|ch: char| ch == ',' || ch.is_whitespace())
+        .filter(|size| !size.is_empty())
+        .map(|size| {
+            size.parse()
+                .ok()
+                .filter(|&size| size > 0 && seen.insert(size))
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|sizes| !sizes.is_empty())
+}
+
+impl RuleBook {
+    /// Parses one rule per line, skipping blank lines.
+    ///
+    /// A grid is split into blocks of the first size that divides its side,
+    /// trying sizes in the order their first rule appears in. A line such as
+    /// `split: 3, 2` declares that order instead, and must then list every
+    /// size that has rules.
+    ///
+    /// Every rule for one size of pattern must have the same size of output,
+    /// and patterns of up to 3x3 must all be matched by exactly one output.
+    /// Larger patterns may go uncovered, in which case enhancing a grid fails
+    /// once it has a block no rule matches. All problems found are reported,
+    /// in the order of the lines they're on.
+    ///
+    /// Rules also match every rotation and flip of their input, as in the
+    /// puzzle.
+    pub fn parse(input: &str) -> Result<Self, Vec<RuleError>> {
+        Self::parse_with(input, Symmetry::default())
+    }
+
+    /// Like [`RuleBook::parse`], but rules match their input under the
+    /// transformations in `symmetry`, and only one rule is kept per orbit.
+    pub fn parse_with(input: &str, symmetry: Symmetry) -> Result<Self, Vec<RuleError>> {
+        let mut drafts = HashMap::new();
+        let mut sizes = Vec::new();
+        let mut split = None;
+        let mut errors = Vec::new();
+
+        for (i, rule) in input.lines().enumerate() {
+            if rule.trim().is_empty() {
+                continue;
+            }
+
+            let line = i + 1;
+            let error = |kind| RuleError {
+                line: Some(line),
+                kind,
+            };
+
+            if let Some(declared) = rule.trim().strip_prefix("split:") {
+                match (split.as_ref(), parse_split(declared)) {
+                    (Some(&(other, _)), _) => {
+                        errors.push(error(RuleErrorKind::DuplicateSplit { line: other }))
+                    }
+                    (None, Some(declared)) => split = Some((line, declared)),
+                    (None, None) => errors.push(error(RuleErrorKind::InvalidSplit)),
+                }
+                continue;
+            }
+
+            let (lhs, rhs) = match parse_rule(rule) {
+                Ok(rule) => rule,
+                Err(kind) => {
+                    errors.push(error(kind));
+                    continue;
+                }
+            };
+
+            let draft = drafts.entry(lhs.side).or_insert_with(|| {
+                sizes.push(lhs.side);
+                Draft {
+                    to: rhs.side,
+                    line,
+                    orbits: HashMap::new(),
+                }
+            });
+
+            if rhs.side != draft.to {
+                errors.push(error(RuleErrorKind::WrongOutputSize {
+                    input: lhs.side,
+                    expected: draft.to,
+                    output: rhs.side,
+                }));
+                continue;
+            }
+
+            let orbit = lhs.canonical(symmetry).bits;
+            match draft.orbits.get(&orbit) {
+                Some(&(output, other)) if output != rhs.bits => {
+                    errors.push(error(RuleErrorKind::Conflict { line: other }))
+                }
+                Some(_) => {}
+                None => {
+                    draft.orbits.insert(orbit, (rhs.bits, line));
+                }
+            }
+        }
+
+        if let Some((split_line, declared)) = split {
+            for &side in &sizes {
+                if !declared.contains(&side) {
+                    errors.push(RuleError {
+                        line: Some(drafts[&side].line),
+                        kind: RuleErrorKind::UndeclaredSize { side, split_line },
+                    });
+                }
+            }
+
+            for &side in &declared {
+                if !drafts.contains_key(&side) {
+                    errors.push(RuleError {
+                        line: Some(split_line),
+                        kind: RuleErrorKind::NoRules(side),
+                    });
+                }
+            }
+
+            sizes = declared;
+            sizes.retain(|side| drafts.contains_key(side));
+        }
+
+        for &side in &sizes {
+            if side > COVERED_MAX_SIDE {
+                continue;
+            }
+
+            for bits in 0..1 << (side * side) {
+                let tile = Tile { side, bits };
+
+                if tile.canonical(symmetry) == tile && !drafts[&side].orbits.contains_key(&bits) {
+                    errors.push(RuleError {
+                        line: None,
+                        kind: RuleErrorKind::Uncovered(tile),
+                    });
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|error| (error.line.is_none(), error.line));
+            return Err(errors);
+        }
+
+        let tables = sizes
+            .into_iter()
+            .map(|side| {
+                let draft = drafts.remove(&side).unwrap();
+                let orbits = draft
+                    .orbits
+                    .into_iter()
+                    .map(|(orbit, (output, _))| (orbit, output))
+                    .collect::<HashMap<_, _>>();
+
+                let direct = (side <= DIRECT_MAX_SIDE).then(|| {
+                    (0..1 << (side * side))
+                        .map(|bits| {
+                            let orbit = Tile { side, bits }.canonical(symmetry).bits;
+                            orbits.get(&orbit).copied()
+                        })
+                        .collect()
+                });
+
+                Table {
+                    from: side,
+                    to: draft.to,
+                    symmetry,
+                    orbits,
+                    direct,
+                }
+            })
+            .collect();
+
+        Ok(Self { symmetry, tables })
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// The rules for the first block size that divides `side`.
+    pub(crate) fn split(&self, side: usize) -> Option<&Table> {
+        self.tables.iter().find(|table| side % table.from == 0)
+    }
+
+    /// Whether grids are split into 2x2 blocks that become 3x3 ones when they
+    /// can be, and into 3x3 blocks that become 4x4 ones otherwise, like the
+    /// puzzle's rules do.
+    pub(crate) fn splits_like_puzzle(&self) -> bool {
+        let sizes = self.tables.iter().map(|table| (table.from, table.to));
+        sizes.take(2).eq([(2, 3), (3, 4)].iter().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnhanceError, Fractal};
+
+    const FOUR_TO_FIVE: &str = "\
+        ..../..../..../.... => ...../...../...../...../.....
+        #.../..../..../.... => #..../...../...../...../.....
+    ";
+
+    #[test]
+    fn large_patterns_may_go_uncovered() {
+        let mut covered = Fractal::builder()
+            .start("#.../..../..../....")
+            .rules(FOUR_TO_FIVE)
+            .build()
+            .unwrap();
+        assert_eq!(covered.step(), Ok(()));
+        assert_eq!(covered.lit_count(), 1);
+
+        let mut uncovered = Fractal::builder()
+            .start("##../..../..../....")
+            .rules(FOUR_TO_FIVE)
+            .build()
+            .unwrap();
+        assert_eq!(
+            uncovered.step(),
+            Err(EnhanceError::Uncovered(
+                "##../..../..../....".parse().unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn reports_every_problem_in_line_order() {
+        let input = "\
+            split: 2, 5
+            ../.. => .../.../...
+            #./.. => #../.../...
+            .#/.. => .../.../...
+            ..../..../..../.... => ...../...../...../...../.....
+            ##/.. => ..../..../..../....
+            #./#. => ##./..
+            #.#/.. => .../.../...
+            ../.. => .../.../...
+
+            ##/## -> ###/###/###
+        ";
+
+        let errors = RuleBook::parse(input).err().unwrap();
+        let error = |line, kind| RuleError { line, kind };
+        let uncovered =
+            |pattern: &str| error(None, RuleErrorKind::Uncovered(pattern.parse().unwrap()));
+        assert_eq!(
+            errors,
+            [
+                error(Some(1), RuleErrorKind::NoRules(5)),
+                error(Some(4), RuleErrorKind::Conflict { line: 3 }),
+                error(
+                    Some(5),
+                    RuleErrorKind::UndeclaredSize {
+                        side: 4,
+                        split_line: 1
+                    }
+                ),
+                error(
+                    Some(6),
+                    RuleErrorKind::WrongOutputSize {
+                        input: 2,
+                        expected: 3,
+                        output: 4
+                    }
+                ),
+                error(
+                    Some(7),
+                    RuleErrorKind::InvalidOutput(PatternError::NotSquare)
+                ),
+                error(
+                    Some(8),
+                    RuleErrorKind::InvalidInput(PatternError::NotSquare)
+                ),
+                error(Some(11), RuleErrorKind::Malformed),
+                uncovered("../##"),
+                uncovered(".#/#."),
+                uncovered(".#/##"),
+                uncovered("##/##"),
+            ]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "line 4: this pattern or an equivalent one has a different output on line 3"
+        );
+        assert_eq!(
+            errors[7].to_string(),
+            "no rule matches `../##` or any pattern equivalent to it"
+        );
+    }
+
+    #[test]
+    fn symmetry_decides_what_conflicts() {
+        let input = "\
+            #./.. => #../.../...
+            .#/.. => .../.../...
+        ";
+
+        let conflicts = |symmetry| {
+            RuleBook::parse_with(input, symmetry)
+                .err()
+                .unwrap()
+                .into_iter()
+                .filter(|error| matches!(error.kind, RuleErrorKind::Conflict { .. }))
+                .count()
+        };
+        assert_eq!(conflicts(Symmetry::D4), 1);
+        assert_eq!(conflicts(Symmetry::C4), 1);
+        assert_eq!(conflicts(Symmetry::None), 0);
+    }
+}
@@ -0,0 +1,279 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use array_iterator::ArrayIterator;
+
+/// The largest side a [`Tile`] can have.
+pub const MAX_SIDE: usize = 8;
+
+/// A square tile of at most 8x8 cells packed into a `u64`, row by row, with the
+/// top-left cell in the most significant of the `side * side` bits it uses.
+///
+/// Tiles are ordered by side, and tiles of one side by their cells row by row
+/// with unlit ones first.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tile {
+    pub(crate) side: usize,
+    pub(crate) bits: u64,
+}
+
+/// Writes cells as `#` and `.`, separating rows with `/`, or with newlines
+/// when formatted with `{:#}`.
+pub(crate) fn write_cells(
+    f: &mut fmt::Formatter<'_>,
+    side: usize,
+    cell: impl Fn(usize, usize) -> bool,
+) -> fmt::Result {
+    for row in 0..side {
+        if row != 0 {
+            if f.alternate() {
+                writeln!(f)?;
+            } else {
+                write!(f, "/")?;
+            }
+        }
+
+        for col in 0..side {
+            if cell(row, col) {
+                write!(f, "#")?;
+            } else {
+                write!(f, ".")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for Tile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_cells(f, self.side, |row, col| self.cell(row, col))
+    }
+}
+
+/// Which ways of turning a pattern over a rule also matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Symmetry {
+    /// Any rotation, with or without a flip.
+    #[default]
+    D4,
+    /// Only rotations.
+    C4,
+    /// Only the pattern itself.
+    None,
+}
+
+/// Why a pattern couldn't be parsed into a [`Tile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// Something other than `.`, `#` or `/` was found.
+    InvalidCell(char),
+    /// Some row doesn't have as many cells as there are rows.
+    NotSquare,
+    /// The pattern has more than [`MAX_SIDE`] rows.
+    TooLarge(usize),
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::InvalidCell(ch) => write!(f, "expected `.` or `#`, found {:?}", ch),
+            PatternError::NotSquare => write!(f, "pattern isn't square"),
+            PatternError::TooLarge(side) => write!(
+                f,
+                "pattern is {0}x{0}, but at most {1}x{1} is supported",
+                side, MAX_SIDE
+            ),
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+impl FromStr for Tile {
+    type Err = PatternError;
+
+    /// Parses a tile in the `.#./..#/###` notation used by the puzzle.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let side = s.split('/').count();
+        if side > MAX_SIDE {
+            return Err(PatternError::TooLarge(side));
+        }
+
+        let mut bits = 0;
+        for row in s.split('/') {
+            if row.chars().count() != side {
+                return Err(PatternError::NotSquare);
+            }
+
+            for ch in row.chars() {
+                let cell = match ch {
+                    '#' => 1,
+                    '.' => 0,
+                    _ => return Err(PatternError::InvalidCell(ch)),
+                };
+                bits = bits << 1 | cell;
+            }
+        }
+
+        Ok(Self { side, bits })
+    }
+}
+
+impl Tile {
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    /// The number of lit cells.
+    pub fn lit_count(&self) -> u32 {
+        self.bits.count_ones()
+    }
+
+    /// The cells of the tile, row by row.
+    pub(crate) fn cells(&self) -> Vec<bool> {
+        (0..self.side)
+            .flat_map(|row| (0..self.side).map(move |col| (row, col)))
+            .map(|(row, col)| self.cell(row, col))
+            .collect()
+    }
+
+    fn shift(&self, row: usize, col: usize) -> usize {
+        self.side * self.side - 1 - (row * self.side + col)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> bool {
+        self.bits >> self.shift(row, col) & 1 == 1
+    }
+
+    /// Moves the cell at each `(row, col)` to `to(row, col)`.
+    fn permute(self, to: impl Fn(usize, usize) -> (usize, usize)) -> Self {
+        let mut bits = 0;
+
+        for row in 0..self.side {
+            for col in 0..self.side {
+                if self.cell(row, col) {
+                    let (row, col) = to(row, col);
+                    bits |= 1 << self.shift(row, col);
+                }
+            }
+        }
+
+        Self { bits, ..self }
+    }
+
+    fn flip_horizontally(self) -> Self {
+        let last = self.side - 1;
+        self.permute(|row, col| (row, last - col))
+    }
+
+    fn rotate_cw(self) -> Self {
+        let last = self.side - 1;
+        self.permute(|row, col| (col, last - row))
+    }
+
+    /// The tile under every rotation, then under a flip followed by every
+    /// rotation.
+    fn possible_transformations(self) -> [Self; 8] {
+        [
+            self,
+            self.rotate_cw(),
+            self.rotate_cw().rotate_cw(),
+            self.rotate_cw().rotate_cw().rotate_cw(),
+            self.flip_horizontally(),
+            self.flip_horizontally().rotate_cw(),
+            self.flip_horizontally().rotate_cw().rotate_cw(),
+            self.flip_horizontally().rotate_cw().rotate_cw().rotate_cw(),
+        ]
+    }
+
+    /// The tile under every transformation in `symmetry`, starting with the
+    /// tile itself.
+    pub fn transformations(self, symmetry: Symmetry) -> impl Iterator<Item = Self> {
+        let order = match symmetry {
+            Symmetry::D4 => 8,
+            Symmetry::C4 => 4,
+            Symmetry::None => 1,
+        };
+        ArrayIterator::new(self.possible_transformations()).take(order)
+    }
+
+    /// The first of the tiles `symmetry` can turn this one into, comparing
+    /// cells row by row with unlit ones first.
+    ///
+    /// Two tiles have the same canonical form exactly when one can be turned
+    /// into the other.
+    pub fn canonical(self, symmetry: Symmetry) -> Self {
+        // The top-left cell is the most significant bit, so comparing the
+        // bits compares the cells in order.
+        self.transformations(symmetry)
+            .min_by_key(|tile| tile.bits)
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMMETRIES: [Symmetry; 3] = [Symmetry::D4, Symmetry::C4, Symmetry::None];
+
+    fn tile(pattern: &str) -> Tile {
+        pattern.parse().unwrap()
+    }
+
+    fn every_tile(side: usize) -> impl Iterator<Item = Tile> {
+        (0..1 << (side * side)).map(move |bits| Tile { side, bits })
+    }
+
+    #[test]
+    fn rotations_alone_never_flip() {
+        let z = tile("##./.##/...");
+        let s = tile(".##/##./...");
+
+        assert!(z
+            .transformations(Symmetry::C4)
+            .any(|other| other == tile("..#/.##/.#.")));
+        assert!(!z.transformations(Symmetry::C4).any(|other| other == s));
+        assert_ne!(z.canonical(Symmetry::C4), s.canonical(Symmetry::C4));
+
+        assert!(z.transformations(Symmetry::D4).any(|other| other == s));
+        assert_eq!(z.canonical(Symmetry::D4), s.canonical(Symmetry::D4));
+    }
+
+    #[test]
+    fn no_symmetry_matches_only_the_pattern() {
+        let z = tile("##./.##/...");
+        assert_eq!(z.transformations(Symmetry::None).collect::<Vec<_>>(), [z]);
+
+        for tile in every_tile(3) {
+            assert_eq!(tile.canonical(Symmetry::None), tile);
+        }
+    }
+
+    #[test]
+    fn canonical_is_the_least_of_the_orbit() {
+        for &symmetry in &SYMMETRIES {
+            for tile in every_tile(2).chain(every_tile(3)) {
+                let canonical = tile.canonical(symmetry);
+                assert_eq!(tile.transformations(symmetry).next(), Some(tile));
+                assert_eq!(Some(canonical), tile.transformations(symmetry).min());
+                for other in tile.transformations(symmetry) {
+                    assert_eq!(other.canonical(symmetry), canonical);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn counts_orbits() {
+        let orbits = |side, symmetry| {
+            every_tile(side)
+                .filter(|&tile| tile.canonical(symmetry) == tile)
+                .count()
+        };
+
+        assert_eq!(orbits(2, Symmetry::D4), 6);
+        assert_eq!(orbits(3, Symmetry::D4), 102);
+        assert_eq!(orbits(3, Symmetry::C4), 140);
+        assert_eq!(orbits(3, Symmetry::None), 512);
+    }
+}
@@ -13,7 +13,7 @@ pub struct State {
 }
 
 fn load_state_transition(lines: impl Iterator<Item = &'static str>) -> StateTransition {
-    let mut last_words = lines.map(|line| line.rsplitn(2, ' ').next().unwrap());
+    let mut last_words = lines.map(|line| line.rsplit(' ').next().unwrap());
 
     let write = match last_words.next().unwrap().parse::<u8>().unwrap() {
         0 => false,